@@ -0,0 +1,145 @@
+/*
+* build.rs
+*
+* guards the stable dex ids
+* every published "ID<TAB>Name" entry in the dex txt files is frozen
+* in dex/ids.lock, if an id disappears or starts meaning something else,
+* every team packed with it would silently decode into the wrong thing,
+* so we fail the build
+*
+* adding an entry means giving it a fresh id in the txt file
+* and appending the same line to dex/ids.lock
+*
+* the older generation folders only list which ids existed back then,
+* every line there has to match the lock exactly as well
+*
+* the data files the dex_build scripts write from @pkmn/dex can't be
+* checked here, but an empty one means the script was never run and
+* everything built on it finds nothing, so we warn about it
+*/
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+};
+
+// generations that are a subset of the full dex, and what they list
+const OLDER_GENS: [&str; 2] = ["gen7", "gen8"];
+const GEN_CATEGORIES: [&str; 4] = ["names", "items", "abilities", "moves"];
+
+// generated file and the npm script in dex_build that writes it
+const GENERATED: [(&str, &str); 3] = [
+    ("dex/learnsets.bin", "learnsets"),
+    ("dex/species.txt", "species"),
+    ("dex/movedata.txt", "moves"),
+];
+
+const CATEGORIES: [&str; 6] = [
+    "names",
+    "items",
+    "abilities",
+    "moves",
+    "natures",
+    "teras",
+];
+
+fn main() {
+    println!("cargo:rerun-if-changed=dex");
+
+    for (path, script) in GENERATED {
+        if fs::metadata(path).map_or(true, |meta| meta.len() == 0) {
+            println!("cargo:warning={} is empty, run `npm run {}` in dex_build", path, script);
+        }
+    }
+
+    let lock = read("dex/ids.lock");
+    let mut frozen: HashMap<(&str, u32), &str> = HashMap::new();
+    for (n, line) in lock.lines().enumerate() {
+        let fields: Vec<&str> = line.splitn(3, '\t').collect();
+        if fields.len() != 3 {
+            fail(&format!("dex/ids.lock:{}: expected CATEGORY<TAB>ID<TAB>Name", n + 1));
+        }
+        let id = parse_id("dex/ids.lock", n, fields[1]);
+        frozen.insert((fields[0], id), fields[2]);
+    }
+
+    for category in CATEGORIES {
+        let path = format!("dex/{}.txt", category);
+        let file = read(&path);
+        let mut ids = HashSet::new();
+        let mut names = HashSet::new();
+
+        for (n, line) in file.lines().enumerate() {
+            let Some((id, name)) = line.split_once('\t') else {
+                fail(&format!("{}:{}: expected ID<TAB>Name", path, n + 1));
+            };
+            let id = parse_id(&path, n, id);
+            if !ids.insert(id) {
+                fail(&format!("{}:{}: duplicate id {}", path, n + 1, id));
+            }
+            // lookups go through showdown's toID, so that's what must be unique
+            if !names.insert(to_id(name)) {
+                fail(&format!("{}:{}: duplicate entry '{}'", path, n + 1, name));
+            }
+            match frozen.get(&(category, id)) {
+                Some(locked) if *locked == name => {},
+                Some(locked) => fail(&format!(
+                    "{}:{}: id {} is frozen as '{}' but is now '{}'",
+                    path, n + 1, id, locked, name
+                )),
+                None => fail(&format!(
+                    "{}:{}: id {} ('{}') is not in dex/ids.lock, append it",
+                    path, n + 1, id, name
+                )),
+            }
+        }
+
+        for (c, id) in frozen.keys() {
+            if *c == category && !ids.contains(id) {
+                fail(&format!("{}: frozen id {} was removed", path, id));
+            }
+        }
+    }
+
+    for gen in OLDER_GENS {
+        for category in GEN_CATEGORIES {
+            let path = format!("dex/{}/{}.txt", gen, category);
+            let file = read(&path);
+            for (n, line) in file.lines().enumerate() {
+                let Some((id, name)) = line.split_once('\t') else {
+                    fail(&format!("{}:{}: expected ID<TAB>Name", path, n + 1));
+                };
+                let id = parse_id(&path, n, id);
+                if frozen.get(&(category, id)) != Some(&name) {
+                    fail(&format!(
+                        "{}:{}: '{}' with id {} is not in dex/ids.lock",
+                        path, n + 1, name, id
+                    ));
+                }
+            }
+        }
+    }
+}
+
+// same as dex::to_id
+fn to_id(s: &str) -> String {
+    s
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn read(path: &str) -> String {
+    fs::read_to_string(path)
+        .unwrap_or_else(|e| fail(&format!("could not read {}: {}", path, e)))
+}
+
+fn parse_id(path: &str, n: usize, id: &str) -> u32 {
+    id.parse()
+        .unwrap_or_else(|_| fail(&format!("{}:{}: bad id '{}'", path, n + 1, id)))
+}
+
+fn fail(msg: &str) -> ! {
+    panic!("dex id check failed: {}", msg)
+}
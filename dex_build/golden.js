@@ -0,0 +1,50 @@
+/*
+ * Writes the expected exports in tests/golden from @pkmn/sim, run with
+ * `npm run golden`. See test_golden_exports in src/export.rs.
+ *
+ * Each <name>.in.txt is imported the way the teambuilder would, then put
+ * through what packing does to a set: names become the dex's names, and
+ * only the generation's own field (Hidden Power, Dynamax or Tera) and no
+ * Pokeball are kept. Teams.export then writes <name>.out.txt.
+ */
+
+import { readdirSync, readFileSync, writeFileSync } from 'node:fs';
+import { Dex, Teams } from '@pkmn/sim';
+
+const dir = '../tests/golden';
+
+// gen7-..., gen8-..., anything else is gen 9, the same as the test
+const generation = (name) => ({ gen7: 7, gen8: 8 })[name.split('-')[0]] ?? 9;
+
+function packed(dex, gen, set) {
+    const name = (table, value) => (value ? table.get(value).name || value : '');
+    set.species = name(dex.species, set.species);
+    // showdown compares the nickname to the species exactly, so do we
+    if (!set.name) set.name = set.species;
+    set.item = name(dex.items, set.item);
+    set.ability = name(dex.abilities, set.ability);
+    set.nature = name(dex.natures, set.nature);
+    set.moves = set.moves.map((move) => name(dex.moves, move));
+    delete set.pokeball;
+    if (gen !== 7) delete set.hpType;
+    if (gen !== 8) {
+        delete set.dynamaxLevel;
+        delete set.gigantamax;
+    }
+    if (gen !== 9) delete set.teraType;
+    return set;
+}
+
+let written = 0;
+for (const file of readdirSync(dir).filter((file) => file.endsWith('.in.txt'))) {
+    const name = file.slice(0, -'.in.txt'.length);
+    const gen = generation(name);
+    const dex = Dex.forGen(gen);
+    const sets = Teams.import(readFileSync(`${dir}/${file}`, 'utf8'));
+    if (!sets) throw new Error(`${file} doesn't import`);
+
+    const team = sets.map((set) => packed(dex, gen, set));
+    writeFileSync(`${dir}/${name}.out.txt`, Teams.export(team));
+    written++;
+}
+console.log(`Golden files: ${written}`);
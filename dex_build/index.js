@@ -1,39 +1,34 @@
 /*
  * This file is a simple script I used to print out all pokemon names, 
  * abilities and moves to build tables from txt files.
+ * Now it also writes the type chart, run with `npm start`.
  */
 
 
 import { strict as assert } from 'node:assert';
+import { writeFileSync } from 'node:fs';
 import {Dex} from '@pkmn/dex';
 
-const allTypes = Dex.forGen(9).types.all();
-//let asd = JSON.stringify(allTypes, null, 2)
-//console.log(asd);
+// writes ../dex/typechart.txt, one line per defending type:
+// Name<TAB>how much it takes from each attacking type, in the same order
+// 0 = neutral, 1 = weak, 2 = resists, 3 = immune, straight from damageTaken
+// the order has to match model::Type in the rust code, stellar is left out
 
-// so I take all the types, go through each one for attack
-const n = allTypes.length;
-for(let i = 0; i < n; i++) {
-    let attackType = allTypes[i];
-    //console.log(attackType.name);
-    // then I take the same loop just for defense
-    for(let j = 0; j < n; j++) {
-        let defenseType = allTypes[j];
-        let d = defenseType.name;
-        if (defenseType.damageTaken.hasOwnProperty(attackType.name)) {
-            console.log(`
-                attack: ${attackType.name} 
-                defense: ${defenseType.name}
-                effect: ${defenseType.damageTaken[d]}
-            `);
-            console.log(defenseType.damageTaken);
-        }
-    }
+const typeOrder = [
+    'Normal', 'Fire', 'Fighting', 'Water', 'Flying', 'Grass',
+    'Poison', 'Electric', 'Ground', 'Psychic', 'Rock', 'Ice',
+    'Bug', 'Dragon', 'Ghost', 'Dark', 'Steel', 'Fairy',
+];
+const types = Dex.forGen(9).types;
+let chart = '';
+for (const defense of typeOrder) {
+    const taken = types.get(defense).damageTaken;
+    chart += `${defense}\t${typeOrder.map(attack => taken[attack]).join('')}\n`;
 }
+writeFileSync('../dex/typechart.txt', chart);
 
-
-
-
+// sanity check against what we know
+assert(Dex.getEffectiveness('Dark', ['Ghost', 'Psychic']) === 2);
 
 function printType(type) {
     console.log(`
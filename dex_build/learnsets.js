@@ -0,0 +1,85 @@
+/*
+ * Writes dex/learnsets.bin from @pkmn/dex, run with `npm run learnsets`.
+ * The format is described at the top of src/learnset.rs.
+ *
+ * Every species in dex/names.txt gets the moves of its prevos and of its
+ * base forme merged in, so the Rust side never has to walk evolutions.
+ * Moves that aren't in dex/moves.txt are skipped.
+ */
+
+import { readFileSync, writeFileSync } from 'node:fs';
+import { Dex } from '@pkmn/dex';
+
+const toID = (s) => s.toLowerCase().replace(/[^a-z0-9]+/g, '');
+
+// "ID<TAB>Name" -> Map(toID(name) => id)
+function readTable(path) {
+    const table = new Map();
+    for (const line of readFileSync(path, 'utf8').split('\n')) {
+        const [id, name] = line.split('\t');
+        if (name) table.set(toID(name), Number(id));
+    }
+    return table;
+}
+
+const names = readTable('../dex/names.txt');
+const moves = readTable('../dex/moves.txt');
+
+// which generations a source like "9M" or "7E" counts for
+// 1 = gen 7 or earlier, 2 = gen 8, 4 = gen 9
+function genBit(source) {
+    const gen = Number(source[0]);
+    if (gen <= 7) return 1;
+    return gen === 8 ? 2 : 4;
+}
+
+// species, its prevos, and the base forme if it has no moves of its own
+async function collect(species, into, seen) {
+    if (!species.exists || seen.has(species.id)) return;
+    seen.add(species.id);
+    const learnset = await Dex.learnsets.get(species.id);
+    const own = learnset?.learnset;
+    if (own) {
+        for (const [move, sources] of Object.entries(own)) {
+            const id = moves.get(move);
+            if (id === undefined) continue;
+            let gens = into.get(id) ?? 0;
+            for (const source of sources) gens |= genBit(source);
+            into.set(id, gens);
+        }
+    }
+    if (!own && species.baseSpecies !== species.name) {
+        await collect(Dex.species.get(species.changesFrom ?? species.baseSpecies), into, seen);
+    }
+    if (species.prevo) {
+        await collect(Dex.species.get(species.prevo), into, seen);
+    }
+}
+
+function varint(value, out) {
+    while (value >= 0x80) {
+        out.push((value & 0x7f) | 0x80);
+        value >>>= 7;
+    }
+    out.push(value);
+}
+
+const bytes = [];
+const sorted = [...names.entries()].sort((a, b) => a[1] - b[1]);
+for (const [name, speciesId] of sorted) {
+    const learnset = new Map();
+    await collect(Dex.species.get(name), learnset, new Set());
+    if (learnset.size === 0) continue;
+
+    const body = [];
+    let previous = 0;
+    for (const [moveId, gens] of [...learnset.entries()].sort((a, b) => a[0] - b[0])) {
+        varint(((moveId - previous) << 3) | gens, body);
+        previous = moveId;
+    }
+    bytes.push(speciesId >> 8, speciesId & 0xff, body.length >> 8, body.length & 0xff);
+    bytes.push(...body);
+}
+
+writeFileSync('../dex/learnsets.bin', Uint8Array.from(bytes));
+console.log(`Learnsets: ${bytes.length} bytes`);
@@ -0,0 +1,23 @@
+/*
+ * Writes dex/movedata.txt from @pkmn/dex, run with `npm run moves`.
+ * The format is described at the top of src/moves.rs.
+ *
+ * Only moves that are already in dex/moves.txt are written, with the
+ * same ids, so nothing here can change what a packed team means.
+ */
+
+import { readFileSync, writeFileSync } from 'node:fs';
+import { Dex } from '@pkmn/dex';
+
+const lines = [];
+for (const line of readFileSync('../dex/moves.txt', 'utf8').split('\n')) {
+    const [id, name] = line.split('\t');
+    if (!name) continue;
+    const move = Dex.moves.get(name);
+    if (!move.exists) continue;
+
+    lines.push([id, move.type, move.category, move.basePower, move.target].join('\t'));
+}
+
+writeFileSync('../dex/movedata.txt', lines.join('\n') + '\n');
+console.log(`Moves: ${lines.length}`);
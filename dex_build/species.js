@@ -0,0 +1,35 @@
+/*
+ * Writes dex/species.txt from @pkmn/dex, run with `npm run species`.
+ * The format is described at the top of src/species.rs.
+ *
+ * Only species that are already in dex/names.txt are written, with the
+ * same ids, so nothing here can change what a packed team means.
+ */
+
+import { readFileSync, writeFileSync } from 'node:fs';
+import { Dex } from '@pkmn/dex';
+
+const toID = (s) => s.toLowerCase().replace(/[^a-z0-9]+/g, '');
+
+const names = new Map();
+for (const line of readFileSync('../dex/names.txt', 'utf8').split('\n')) {
+    const [id, name] = line.split('\t');
+    if (name) names.set(toID(name), Number(id));
+}
+
+const lines = [];
+for (const [name, id] of [...names.entries()].sort((a, b) => a[1] - b[1])) {
+    const species = Dex.species.get(name);
+    if (!species.exists) continue;
+
+    const bs = species.baseStats;
+    const stats = [bs.hp, bs.atk, bs.def, bs.spa, bs.spd, bs.spe].join('/');
+    const a = species.abilities;
+    const abilities = [a['0'] ?? '', a['1'] ?? '', a['H'] ?? ''].join('/');
+    const base = names.get(toID(species.baseSpecies)) ?? id;
+
+    lines.push([id, stats, species.types.join('/'), abilities, species.weighthg, base].join('\t'));
+}
+
+writeFileSync('../dex/species.txt', lines.join('\n') + '\n');
+console.log(`Species: ${lines.length}`);
@@ -16,6 +16,7 @@ async function main() {
 
     function buildUI() {
         const pasteInput = createElement('textarea', { id: 'paste-input', placeholder: 'Paste your pokepaste here...', rows: 15 });
+        const pasteError = createElement('p', { id: 'paste-error', className: 'error-message' });
         const compressBtn = createElement('button', { id: 'compress-btn', textContent: 'Compress' });
         const formatSelect = createElement('select', { id: 'format-select', innerHTML: `<option value="base64" selected>Base64</option><option value="hex">Hex</option>` });
         const compressedOutput = createElement('textarea', { id: 'compressed-output', placeholder: 'Compressed output...', readOnly: true, rows: 12 });
@@ -24,6 +25,7 @@ async function main() {
         const decompressBtn = createElement('button', { id: 'decompress-btn', textContent: 'Decompress' });
         const decompressedOutput = createElement('textarea', { id: 'decompressed-output', placeholder: 'Decompressed pokepaste...', readOnly: true, rows: 12 });
         const copyDecompressedBtn = createElement('button', { className: 'copy-btn', textContent: 'Copy' });
+        const analysisOutput = createElement('div', { id: 'analysis-output', className: 'analysis' });
 
         const container = createElement('div', { className: 'container', children: [
             createElement('header', { innerHTML: `<h1>PokéPack</h1><p>Compress and decompress Pokémon Showdown pastes with WebAssembly.</p>` }),
@@ -31,6 +33,7 @@ async function main() {
                 createElement('div', { className: 'column', children: [
                     createElement('h2', { textContent: 'Compress' }),
                     pasteInput,
+                    pasteError,
                     createElement('div', { className: 'controls', children: [compressBtn, formatSelect] }),
                     createElement('div', { className: 'output-wrapper', children: [compressedOutput, copyCompressedBtn] })
                 ]}),
@@ -38,7 +41,8 @@ async function main() {
                     createElement('h2', { textContent: 'Decompress' }),
                     compressedInput,
                     createElement('div', { className: 'controls', children: [decompressBtn] }),
-                    createElement('div', { className: 'output-wrapper', children: [decompressedOutput, copyDecompressedBtn] })
+                    createElement('div', { className: 'output-wrapper', children: [decompressedOutput, copyDecompressedBtn] }),
+                    analysisOutput
                 ]})
             ]}),
             createElement('footer', { innerHTML: `<p>Compression Ratio: <span id="compression-ratio">N/A</span></p>` })
@@ -46,7 +50,7 @@ async function main() {
 
         document.body.append(container);
         
-        return { pasteInput, compressedOutput, compressBtn, formatSelect, copyCompressedBtn, compressedInput, decompressedOutput, decompressBtn, copyDecompressedBtn };
+        return { pasteInput, pasteError, compressedOutput, compressBtn, formatSelect, copyCompressedBtn, compressedInput, decompressedOutput, decompressBtn, copyDecompressedBtn, analysisOutput };
     }
 
     function copyToClipboard(textarea, button) {
@@ -65,12 +69,60 @@ async function main() {
         });
     }
     
+    // the same line by line decoding the wasm side does, hex first then base64
+    function compressedToBytes(compressed) {
+        const lines = compressed.split('\n').map(line => line.trim()).filter(line => line);
+        const hex = lines.every(line => /^([0-9a-fA-F]{2})+$/.test(line));
+        const chunks = lines.map(line => hex
+            ? Uint8Array.from(line.match(/../g), byte => parseInt(byte, 16))
+            : Uint8Array.from(atob(line), c => c.charCodeAt(0)));
+        const bytes = new Uint8Array(chunks.reduce((len, chunk) => len + chunk.length, 0));
+        let offset = 0;
+        for (const chunk of chunks) {
+            bytes.set(chunk, offset);
+            offset += chunk.length;
+        }
+        return bytes;
+    }
+
+    // weaknesses and coverage of the decoded team, see analysis.rs
+    function renderAnalysis(analysis) {
+        // "Water ×2, Ground ×2", a member without types has no table at all
+        const typeList = (table, keep) => Object.entries(table || {})
+            .filter(([, multiplier]) => keep(multiplier))
+            .map(([type, multiplier]) => `${type} ×${multiplier}`)
+            .join(', ') || '-';
+        const row = (tag, cells) => createElement('tr', {
+            children: cells.map(text => createElement(tag, { textContent: text }))
+        });
+        const rows = analysis.members.map(member => row('td', [
+            member.species,
+            member.types.length ? member.types.join('/') : 'no type data',
+            member.tera || '-',
+            typeList(member.defense, multiplier => multiplier > 1),
+            typeList(member.defense, multiplier => multiplier < 1),
+            member.moveTypes.join(', ') || '-'
+        ]));
+        const none = list => list.length ? list.join(', ') : 'none';
+
+        ui.analysisOutput.replaceChildren(
+            createElement('h3', { textContent: 'Analysis' }),
+            createElement('table', { children: [
+                row('th', ['Pokémon', 'Types', 'Tera', 'Weak to', 'Resists', 'Move types']),
+                ...rows
+            ]}),
+            createElement('p', { textContent: `Stacked weaknesses: ${none(analysis.stackedWeaknesses)}` }),
+            createElement('p', { textContent: `Nothing super effective against: ${none(analysis.uncovered)}` })
+        );
+    }
+
     const ui = buildUI();
 
     // --- Event Listeners ---
     ui.compressBtn.addEventListener('click', () => {
         const paste = ui.pasteInput.value;
         if (!paste.trim()) return;
+        ui.pasteError.textContent = '';
         try {
             const result = (ui.formatSelect.value === 'base64')
                 ? wasm_bindgen.pokepaste_to_base64(paste)
@@ -80,13 +132,16 @@ async function main() {
             const compressedSize = new TextEncoder().encode(result).length;
             document.getElementById('compression-ratio').textContent = `${(originalSize / compressedSize).toFixed(2)}:1`;
         } catch (e) {
-            ui.compressedOutput.value = `Error: ${e}`;
+            // unknown names come with "did you mean" suggestions
+            ui.compressedOutput.value = '';
+            ui.pasteError.textContent = `${e}`;
         }
     });
 
     ui.decompressBtn.addEventListener('click', () => {
         const compressed = ui.compressedInput.value.trim();
         if (!compressed) return;
+        ui.analysisOutput.replaceChildren();
         try {
             ui.decompressedOutput.value = wasm_bindgen.hex_to_pokepaste(compressed);
         } catch (hexError) {
@@ -94,8 +149,15 @@ async function main() {
                 ui.decompressedOutput.value = wasm_bindgen.base64_to_pokepaste(compressed);
             } catch (base64Error) {
                 ui.decompressedOutput.value = "Error: Failed to decode. Input must be a valid Hex or Base64 string.";
+                return;
             }
         }
+        // the paste is already out, a team that can't be analysed still decodes
+        try {
+            renderAnalysis(wasm_bindgen.decode_team_analysis(compressedToBytes(compressed)));
+        } catch (e) {
+            console.error('Failed to analyse team: ', e);
+        }
     });
 
     ui.copyCompressedBtn.addEventListener('click', () => copyToClipboard(ui.compressedOutput, ui.copyCompressedBtn));
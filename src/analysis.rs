@@ -0,0 +1,283 @@
+/*
+* analysis.rs
+*
+* what a team is weak to and what it can hit, the stuff we'd otherwise
+* eyeball off a type chart (typechart.rs)
+* types come from dex/species.txt and move types from dex/movedata.txt,
+* a species or move without data is left out of the numbers (its member
+* has no types or no move types), while those files are still empty
+* that's everything and build.rs warns about it
+* abilities (levitate, flash fire, ...) and items aren't taken into account
+*/
+
+use serde::Serialize;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    dex::Dex,
+    model::Type,
+    parser::Pokemon,
+    typechart::{TypeTable, CHART_TYPES},
+};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPESCRIPT: &str = r#"
+export type TypeTable<T> = Record<string, T>;
+
+export interface MemberAnalysis {
+    species: string;
+    types: string[];
+    tera: string | null;
+    defense: TypeTable<number> | null;
+    teraDefense: TypeTable<number> | null;
+    moveTypes: string[];
+}
+
+export interface WeaknessCount {
+    weak: number;
+    resist: number;
+    immune: number;
+}
+
+export interface TeamAnalysis {
+    members: MemberAnalysis[];
+    weaknesses: TypeTable<WeaknessCount>;
+    stackedWeaknesses: string[];
+    coverage: TypeTable<number>;
+    uncovered: string[];
+}
+"#;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberAnalysis {
+    pub species:        String,
+    // empty when the dex has no data for the species
+    pub types:          Vec<Type>,
+    pub tera:           Option<Type>,
+    // what each attacking type does to it, None without types
+    pub defense:        Option<TypeTable<f32>>,
+    // the same once it terastallizes, stellar keeps its own types
+    pub tera_defense:   Option<TypeTable<f32>>,
+    // types of its damaging moves, tera blast takes the tera type
+    pub move_types:     Vec<Type>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct WeaknessCount {
+    pub weak:   usize,
+    pub resist: usize,
+    pub immune: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamAnalysis {
+    pub members:            Vec<MemberAnalysis>,
+    // per attacking type, how the team takes it before tera
+    pub weaknesses:         TypeTable<WeaknessCount>,
+    // attacking types more members are weak to than resist or are immune to
+    pub stacked_weaknesses: Vec<Type>,
+    // best multiplier any move on the team gets on each single type
+    pub coverage:           TypeTable<f32>,
+    // types nothing on the team hits super effectively
+    pub uncovered:          Vec<Type>,
+}
+
+pub fn analyse_member(dex: &Dex, pokemon: &Pokemon) -> MemberAnalysis {
+    let types: Vec<Type> = dex
+        .species_data(&pokemon.name)
+        .map(|species| species.types.to_vec())
+        .unwrap_or_default();
+    let chart = &dex.type_chart;
+    let defense = (!types.is_empty()).then(|| chart.defense(&types));
+    let tera_defense = match pokemon.tera {
+        Some(Type::Stellar) => defense,
+        Some(tera) => Some(chart.defense(&[tera])),
+        None => None,
+    };
+
+    let mut move_types = Vec::new();
+    for m in &pokemon.moves {
+        let move_type = match pokemon.tera {
+            Some(tera) if m.id() == "terablast" => Some(tera),
+            _ => dex
+                .move_data(m.name())
+                .filter(|data| data.is_damaging())
+                .map(|data| data.move_type),
+        };
+        if let Some(t) = move_type.filter(|t| !move_types.contains(t)) {
+            move_types.push(t);
+        }
+    }
+
+    MemberAnalysis {
+        species: pokemon.name.clone(),
+        types,
+        tera: pokemon.tera,
+        defense,
+        tera_defense,
+        move_types,
+    }
+}
+
+pub fn analyse_team(dex: &Dex, team: &[Pokemon]) -> TeamAnalysis {
+    let members: Vec<MemberAnalysis> = team
+        .iter()
+        .map(|pokemon| analyse_member(dex, pokemon))
+        .collect();
+
+    let mut weaknesses = TypeTable::<WeaknessCount>::default();
+    for defense in members.iter().filter_map(|member| member.defense.as_ref()) {
+        for attack in CHART_TYPES {
+            let count = &mut weaknesses[attack];
+            match defense[attack] {
+                0.0 => count.immune += 1,
+                m if m > 1.0 => count.weak += 1,
+                m if m < 1.0 => count.resist += 1,
+                _ => {},
+            }
+        }
+    }
+    let stacked_weaknesses = CHART_TYPES
+        .into_iter()
+        .filter(|&t| weaknesses[t].weak > weaknesses[t].resist + weaknesses[t].immune)
+        .collect();
+
+    // no damaging moves at all means no coverage, not neutral coverage
+    let coverage = TypeTable::from_fn(|defense| {
+        members
+            .iter()
+            .flat_map(|member| &member.move_types)
+            .map(|&attack| dex.type_chart.effectiveness(attack, &[defense]))
+            .fold(0.0, f32::max)
+    });
+    let uncovered = CHART_TYPES
+        .into_iter()
+        .filter(|&t| coverage[t] < 2.0)
+        .collect();
+
+    TeamAnalysis {
+        members,
+        weaknesses,
+        stacked_weaknesses,
+        coverage,
+        uncovered,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dex::Generation, moves, parser, species};
+
+    fn test_dex() -> Dex {
+        let mut dex = Dex::build(Generation::Gen9).unwrap();
+        let names = &dex.maps.names;
+        let species = format!(
+            "{}\t95/115/90/80/90/60\tFire/Dark\tBlaze//Intimidate\t830\t{0}\n\
+             {}\t108/130/95/80/85/102\tDragon/Ground\tSand Veil//Rough Skin\t950\t{1}\n\
+             {}\t85/60/65/135/105/100\tFire/Steel\tFlash Fire//Flame Body\t4300\t{2}\n",
+            names["incineroar"], names["garchomp"], names["heatran"]
+        );
+        dex.species = species::parse_species(&species, &dex.maps.abilities, true).unwrap();
+        let moves = &dex.maps.moves;
+        let move_data = format!(
+            "{}\tFire\tPhysical\t120\tnormal\n{}\tGround\tPhysical\t100\tallAdjacent\n\
+             {}\tDragon\tPhysical\t80\tnormal\n{}\tNormal\tStatus\t0\tself\n\
+             {}\tNormal\tSpecial\t80\tnormal\n",
+            moves["flareblitz"], moves["earthquake"], moves["dragonclaw"],
+            moves["protect"], moves["terablast"]
+        );
+        dex.move_data = moves::parse_moves(&move_data).unwrap();
+        dex
+    }
+
+    #[test]
+    fn test_analyse_member() {
+        let dex = test_dex();
+        let paste = "Incineroar\nTera Type: Grass\n- Flare Blitz\n- Protect\n- Tera Blast\n\n\
+                     Garchomp\nTera Type: Stellar\n- Earthquake\n- Dragon Claw\n- Earthquake\n\n\
+                     Pikachu\n- Thunderbolt";
+        let team = parser::parse_pokepaste(paste.to_string()).unwrap();
+
+        let incineroar = analyse_member(&dex, &team[0]);
+        assert_eq!(incineroar.types, [Type::Fire, Type::Dark]);
+        assert_eq!(incineroar.move_types, [Type::Fire, Type::Grass]);
+        let defense = incineroar.defense.unwrap();
+        assert_eq!(defense[Type::Psychic], 0.0);
+        assert_eq!(defense[Type::Water], 2.0);
+        assert_eq!(incineroar.tera_defense.unwrap()[Type::Water], 0.5);
+
+        let garchomp = analyse_member(&dex, &team[1]);
+        assert_eq!(garchomp.move_types, [Type::Ground, Type::Dragon]);
+        assert_eq!(garchomp.defense.unwrap()[Type::Ice], 4.0);
+        assert_eq!(garchomp.tera_defense, garchomp.defense);
+
+        // nothing known about pikachu or thunderbolt here
+        let pikachu = analyse_member(&dex, &team[2]);
+        assert!(pikachu.types.is_empty());
+        assert_eq!(pikachu.defense, None);
+        assert!(pikachu.move_types.is_empty());
+    }
+
+    #[test]
+    fn test_analyse_team() {
+        let dex = test_dex();
+        let paste = "Incineroar\n- Flare Blitz\n\nGarchomp\n- Earthquake\n\nHeatran";
+        let team = parser::parse_pokepaste(paste.to_string()).unwrap();
+        let analysis = analyse_team(&dex, &team);
+
+        // garchomp takes ground neutrally, heatran takes it 4x
+        let ground = analysis.weaknesses[Type::Ground];
+        assert_eq!(ground, WeaknessCount { weak: 2, resist: 0, immune: 0 });
+        let electric = analysis.weaknesses[Type::Electric];
+        assert_eq!(electric, WeaknessCount { weak: 0, resist: 0, immune: 1 });
+        assert!(analysis.stacked_weaknesses.contains(&Type::Ground));
+        assert!(analysis.stacked_weaknesses.contains(&Type::Water));
+        assert!(!analysis.stacked_weaknesses.contains(&Type::Electric));
+
+        assert_eq!(analysis.coverage[Type::Steel], 2.0);
+        assert_eq!(analysis.coverage[Type::Flying], 1.0);
+        assert_eq!(analysis.coverage[Type::Dragon], 1.0);
+        assert!(analysis.uncovered.contains(&Type::Flying));
+        assert!(!analysis.uncovered.contains(&Type::Electric));
+
+        let json = serde_json::to_value(&analysis).unwrap();
+        assert_eq!(json["weaknesses"]["Ground"]["weak"], 2);
+        assert_eq!(json["members"][0]["types"][1], "Dark");
+        assert_eq!(json["members"][0]["teraDefense"], serde_json::Value::Null);
+        assert_eq!(json["coverage"]["Steel"], 2.0);
+    }
+
+    #[test]
+    #[ignore = "needs dex/species.txt and dex/movedata.txt, see dex_build"]
+    fn test_shipped_coverage() {
+        let dex = crate::get_dex();
+        let paste = "Incineroar\nTera Type: Grass\n- Flare Blitz\n- Knock Off\n- Fake Out\n\
+                     - Parting Shot\n\n\
+                     Garchomp\n- Earthquake\n- Dragon Claw\n- Protect";
+        let team = parser::parse_pokepaste(paste.to_string()).unwrap();
+        let analysis = analyse_team(dex, &team);
+
+        let incineroar = &analysis.members[0];
+        assert_eq!(incineroar.types, [Type::Fire, Type::Dark]);
+        assert_eq!(incineroar.move_types, [Type::Fire, Type::Dark, Type::Normal]);
+        assert_eq!(analysis.members[1].move_types, [Type::Ground, Type::Dragon]);
+        assert_eq!(analysis.coverage[Type::Steel], 2.0);
+        assert_eq!(analysis.coverage[Type::Ghost], 2.0);
+        assert_eq!(analysis.coverage[Type::Flying], 1.0);
+        assert!(analysis.uncovered.contains(&Type::Water));
+        assert_eq!(analysis.weaknesses[Type::Ground].weak, 1);
+        assert_eq!(analysis.weaknesses[Type::Ice].weak, 1);
+    }
+
+    #[test]
+    fn test_no_moves_no_coverage() {
+        let dex = test_dex();
+        let team = parser::parse_pokepaste("Heatran".to_string()).unwrap();
+        let analysis = analyse_team(&dex, &team);
+        assert_eq!(analysis.coverage, TypeTable([0.0; 18]));
+        assert_eq!(analysis.uncovered, CHART_TYPES);
+    }
+}
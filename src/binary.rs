@@ -9,6 +9,11 @@
 
 use std::fmt;
 
+use crate::{
+    dex::Generation,
+    model::{Stats, MAX_MOVES},
+};
+
 // see if we add this up without bit packing -> 241 bits?
 // therefore we must pack it
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -20,10 +25,26 @@ pub struct PokemonBin {
     pub level:      u8,
     pub shiny:      bool,
     pub tera:       u8,
-    pub evs:        TvBin,
+    // training values either 0-31 or 0-255 which fits in u8
+    pub evs:        Stats<u8>,
     pub nature:     u8,
-    pub ivs:        TvBin,
-    pub moves:      Vec<u16>, // we will just encode the first 4 for ease
+    pub ivs:        Stats<u8>,
+    // 0 is an empty slot
+    pub moves:      [u16; MAX_MOVES],
+    // not part of the 21 byte record, see envelope.rs
+    pub nickname:   String,
+    pub happiness:  Option<u8>,
+}
+
+// a whole team, only the records go through the bit packing below
+// the metadata, nicknames and happiness are written around them,
+// see envelope.rs
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TeamBin {
+    pub generation: Generation,
+    pub format:     String,
+    pub name:       String,
+    pub pokemon:    Vec<PokemonBin>,
 }
 
 // simple printer
@@ -39,9 +60,9 @@ impl fmt::Display for PokemonBin {
             self.level,
             if self.shiny {1} else {0},
             self.tera,
-            self.evs,
+            hex_stats(&self.evs),
             self.nature,
-            self.ivs,
+            hex_stats(&self.ivs),
             self.moves[0],
             self.moves[1],
             self.moves[2],
@@ -50,31 +71,15 @@ impl fmt::Display for PokemonBin {
     }
 }
 
-// training values either 0-31 or 0-255 which fits in u8
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
-pub struct TvBin {
-    pub hp:     u8, 
-    pub atk:    u8,
-    pub def:    u8,
-    pub spa:    u8,
-    pub spd:    u8,
-    pub spe:    u8,
+fn hex_stats(stats: &Stats<u8>) -> String {
+    stats
+        .to_array()
+        .map(|v| format!("{:04X}", v))
+        .join(" ")
 }
 
-impl fmt::Display for TvBin {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{:04X} {:04X} {:04X} {:04X} {:04X} {:04X}",
-            self.hp,
-            self.atk,
-            self.def,
-            self.spa,
-            self.spd,
-            self.spe,
-        ) 
-    }
-}
+// size of one packed pokemon record
+pub const RECORD_BYTES: usize = 21;
 
 // these are the bit shifts we perform
 // group u32
@@ -94,7 +99,7 @@ const MOVE_BITS: u32 = 10;
 
 
 impl PokemonBin {
-    pub fn pack_to_bytes(&self) -> [u8; 21] {
+    pub fn pack_to_bytes(&self) -> [u8; RECORD_BYTES] {
         let mut group1: u32 = 0;
         let mut group2: u8 = 0;
         let mut group3: u128 = 0;
@@ -108,42 +113,25 @@ impl PokemonBin {
         group1 <<= ITEM_BITS;       group1 |= self.item as u32;
         group1 <<= ABILITY_BITS;    group1 |= self.ability as u32;
 
-        group2 |= self.level as u8;
+        group2 |= self.level;
         group2 <<= SHINY_BITS;      group2 |= if self.shiny { 1 } else { 0 };
 
         group3 |= self.tera as u128;
-        let evs = [
-            self.evs.hp, 
-            self.evs.atk, 
-            self.evs.def, 
-            self.evs.spa, 
-            self.evs.spd, 
-            self.evs.spe
-        ];
-        for ev in evs {
+        for ev in self.evs.to_array() {
             group3 <<= EV_BITS;     group3 |= ev as u128;
         }
 
         group3 <<= NATURE_BITS;     group3 |= self.nature as u128;
 
-        let ivs = [
-            self.ivs.hp, 
-            self.ivs.atk, 
-            self.ivs.def, 
-            self.ivs.spa, 
-            self.ivs.spd, 
-            self.ivs.spe
-        ];
-        for iv in ivs {
+        for iv in self.ivs.to_array() {
             group3 <<= IV_BITS;      group3 |= iv as u128;
         }
 
-        for i in 0..4 {
-            let move_id = self.moves.get(i).cloned().unwrap_or(0);
+        for move_id in self.moves {
             group3 <<= MOVE_BITS;   group3 |= move_id as u128;
         }
 
-        let mut result = [0u8; 21];
+        let mut result = [0u8; RECORD_BYTES];
         result[0..4].copy_from_slice(&group1.to_be_bytes());
         result[4..5].copy_from_slice(&group2.to_be_bytes());
         result[5..21].copy_from_slice(&group3.to_be_bytes());
@@ -155,7 +143,7 @@ impl PokemonBin {
 
 // this is just pack but in reverse
 // start from group 3 and go backwards
-pub fn unpack_from_bytes(bytes: &[u8; 21]) -> PokemonBin {
+pub fn unpack_from_bytes(bytes: &[u8; RECORD_BYTES]) -> PokemonBin {
     // have to reconstruct the integer groups from the u8 array
     let mut group1_bytes = [0u8; 4];
     group1_bytes.copy_from_slice(&bytes[0..4]);
@@ -171,29 +159,26 @@ pub fn unpack_from_bytes(bytes: &[u8; 21]) -> PokemonBin {
     let mut pbin = PokemonBin::default();
 
     // unpack group 3 u128 in reverse
-    let mut moves = Vec::with_capacity(4);
-    for _ in 0..4 {
-        moves.insert(0, (group3 & ((1 << MOVE_BITS) - 1)) as u16);
+    for move_id in pbin.moves.iter_mut().rev() {
+        *move_id = (group3 & ((1 << MOVE_BITS) - 1)) as u16;
         group3 >>= MOVE_BITS;
     }
-    pbin.moves = moves;
 
-    pbin.ivs.spe = (group3 & ((1 << IV_BITS) - 1)) as u8; group3 >>= IV_BITS;
-    pbin.ivs.spd = (group3 & ((1 << IV_BITS) - 1)) as u8; group3 >>= IV_BITS;
-    pbin.ivs.spa = (group3 & ((1 << IV_BITS) - 1)) as u8; group3 >>= IV_BITS;
-    pbin.ivs.def = (group3 & ((1 << IV_BITS) - 1)) as u8; group3 >>= IV_BITS;
-    pbin.ivs.atk = (group3 & ((1 << IV_BITS) - 1)) as u8; group3 >>= IV_BITS;
-    pbin.ivs.hp  = (group3 & ((1 << IV_BITS) - 1)) as u8; group3 >>= IV_BITS;
+    // spe first, same as the moves
+    let mut ivs = [0u8; 6];
+    for iv in ivs.iter_mut().rev() {
+        *iv = (group3 & ((1 << IV_BITS) - 1)) as u8; group3 >>= IV_BITS;
+    }
+    pbin.ivs = Stats::from_array(ivs);
 
     pbin.nature = (group3 & ((1 << NATURE_BITS) - 1)) as u8;
     group3 >>= NATURE_BITS;
 
-    pbin.evs.spe = (group3 & ((1 << EV_BITS) - 1)) as u8; group3 >>= EV_BITS;
-    pbin.evs.spd = (group3 & ((1 << EV_BITS) - 1)) as u8; group3 >>= EV_BITS;
-    pbin.evs.spa = (group3 & ((1 << EV_BITS) - 1)) as u8; group3 >>= EV_BITS;
-    pbin.evs.def = (group3 & ((1 << EV_BITS) - 1)) as u8; group3 >>= EV_BITS;
-    pbin.evs.atk = (group3 & ((1 << EV_BITS) - 1)) as u8; group3 >>= EV_BITS;
-    pbin.evs.hp  = (group3 & ((1 << EV_BITS) - 1)) as u8; group3 >>= EV_BITS;
+    let mut evs = [0u8; 6];
+    for ev in evs.iter_mut().rev() {
+        *ev = (group3 & ((1 << EV_BITS) - 1)) as u8; group3 >>= EV_BITS;
+    }
+    pbin.evs = Stats::from_array(evs);
 
     pbin.tera = (group3 & ((1 << TERA_BITS) - 1)) as u8;
 
@@ -227,7 +212,7 @@ mod tests {
             level: 100,
             shiny: true,
             tera: 10,       // psychic
-            evs: TvBin {
+            evs: Stats {
                 hp: 4,
                 atk: 0,
                 def: 252,
@@ -236,7 +221,7 @@ mod tests {
                 spe: 0,
             },
             nature: 15,      // modest
-            ivs: TvBin {
+            ivs: Stats {
                 hp: 31,
                 atk: 0,
                 def: 31,
@@ -244,7 +229,9 @@ mod tests {
                 spd: 31,
                 spe: 31,
             },
-            moves: vec![305, 109, 157, 799], // skill swap, recover, explosion, expanding force
+            moves: [305, 109, 157, 799], // skill swap, recover, explosion, expanding force
+            nickname: String::new(),
+            happiness: None,
         }
     }
 
@@ -5,149 +5,364 @@
 * see binary.rs and parser.rs for the struct definitions
 */
 
-use std::collections::HashMap;
+use std::{collections::HashMap, str::FromStr};
+
+use arrayvec::ArrayVec;
 
 use crate::{
-    dex::{Tables, Maps},
-    parser::{Pokemon, Tv},
-    binary::{PokemonBin, TvBin},
+    dex::{self, Dex, DexId, EntryKind, Generation},
+    parser::{self, Pokemon, Team},
+    binary::{PokemonBin, TeamBin},
+    error::EncodeError,
+    model::{Gender, MoveId, Nature, Type, MAX_DYNAMAX_LEVEL, MAX_HAPPINESS, MAX_MOVES},
+    species::ABILITY_SLOTS,
+    suggest::{self, MAX_SUGGESTIONS},
 };
 
-// standard function, returns usize
+// standard function, returns the stable dex id
 // cast to the correct u-int size in PokemonBin
-fn element_to_binary(map: &HashMap<String, usize>, element: &str) -> usize {
-    // we convert to lowercase because that is how we built our hashmap
-    match map.get(&element.to_lowercase()) {
-        Some(i) => *i,
-        None    => 0,
-    }
+fn element_to_binary(
+    map: &HashMap<String, DexId>, 
+    element: &str
+) -> Option<DexId> {
+    // we convert to the showdown id because that is how we built our hashmap
+    map.get(&dex::to_id(element)).copied()
 }
 
-// our chill o(1) lookup?
-// clone is fine
-fn binary_to_element(table: &Vec<String>, index: usize) -> String {
-    table[index].clone()
+// looks up every name of one pokemon block
+// anything missing is written down instead of silently becoming id 0,
+// along with the closest names from the dex,
+// the caller decides whether that is an error or a warning
+struct Lookup<'a> {
+    dex: &'a Dex,
+    block_index: usize,
+    unknown: &'a mut Vec<EncodeError>,
 }
 
-fn gender_to_binary(gender: &str) -> u8 {
-    // make sure it is lowercase for comparison
-    // male, female or genderless
-    match gender.to_lowercase().as_str() {
-        "m" => 0,
-        "f" => 1,
-        _   => 2,
+impl Lookup<'_> {
+    fn get(&mut self, kind: EntryKind, element: &str) -> DexId {
+        match element_to_binary(self.dex.maps.get(kind), element) {
+            Some(id) => id,
+            None => {
+                self.unknown.push(EncodeError::UnknownEntry {
+                    kind,
+                    value: element.to_string(),
+                    block_index: self.block_index,
+                    suggestions: suggest::suggest(
+                        self.dex.tables.get(kind), 
+                        element, 
+                        MAX_SUGGESTIONS
+                    ),
+                });
+                0
+            },
+        }
+    }
+
+    // the field exists, just not in the generation we are packing for
+    fn not_in_generation(&mut self, field: &'static str) {
+        self.unknown.push(EncodeError::NotInGeneration {
+            field,
+            generation: self.dex.generation,
+            block_index: self.block_index,
+        });
     }
 }
 
-fn binary_to_gender(gender: u8) -> String {
-    match gender {
-        0 => "m".to_string(),
-        1 => "f".to_string(),
-        _ => "".to_string(),
-    }
-}
-
-// need to check if IV so that it defaults to 31 "perfect"
-// which is the intended behavior
-// need to add conditions for when number is greater than 255
-fn small_to_u8(s: &str, ifiv: bool) -> u8 {
-    //println!("test level -{}-", level);
-    if s == "" {
-        if ifiv {
-            31 
-        } else {
-            0
+// gen 9 keeps the tera type in the 5 bit tera slot
+// gens 7 and 8 have no tera, so the same bits hold what they had instead
+// gen 8: gigantamax flag on top of the dynamax level (0-10)
+// gen 7: hidden power type, from the same type list as tera
+const GIGANTAMAX_BIT: u8 = 1 << 4;
+const DYNAMAX_LEVEL_MASK: u8 = GIGANTAMAX_BIT - 1;
+// showdown leaves "Dynamax Level" out when it's 10
+const DEFAULT_DYNAMAX_LEVEL: u8 = MAX_DYNAMAX_LEVEL;
+
+// no type is the empty entry at the end of the tera table
+fn type_name(t: Option<Type>) -> &'static str {
+    t.map(Type::name).unwrap_or_default()
+}
+
+fn encode_gen_slot(l: &mut Lookup, pokemon: &Pokemon) -> u8 {
+    let generation = l.dex.generation;
+    // anything that belongs to another gen has nowhere to go
+    let fields = [
+        ("Tera Type", pokemon.tera.is_some(), Generation::Gen9),
+        ("Dynamax Level", pokemon.dynamax_level.is_some(), Generation::Gen8),
+        ("Gigantamax", pokemon.gigantamax, Generation::Gen8),
+        ("Hidden Power", pokemon.hidden_power.is_some(), Generation::Gen7),
+    ];
+    for (field, present, owner) in fields {
+        if present && owner != generation {
+            l.not_in_generation(field);
         }
-    } else {
-        // not sure if this is the error behavior we want
-        s.trim().parse::<u8>().unwrap_or(0)  
-    }
-}
-
-// we use the .into() to convert to usize
-pub fn pokebin_to_string(tables: &Tables, pbin: &PokemonBin) -> Pokemon {
-    Pokemon {
-        name:       binary_to_element(&tables.names, pbin.name.into()),
-        gender:     binary_to_gender(pbin.gender.into()),
-        item:       binary_to_element(&tables.items, pbin.item.into()),
-        ability:    binary_to_element(&tables.abilities, pbin.ability.into()),
-        level:      if pbin.level == 0 {"".into()} else {pbin.level.to_string()},
-        shiny:      if pbin.shiny { "Yes".to_string() } else { "".to_string() },
-        tera:       binary_to_element(&tables.teras, pbin.tera.into()),
-        evs:        decode_tvs(&pbin.evs, false),
-        nature:     binary_to_element(&tables.natures, pbin.nature.into()),
-        ivs:        decode_tvs(&pbin.ivs, true),
-        moves:      decode_moves(&tables.moves, &pbin.moves),
+    }
+
+    match generation {
+        Generation::Gen9 => l.get(EntryKind::Tera, type_name(pokemon.tera)) as u8,
+        Generation::Gen8 => {
+            // the parser already checked it, this just keeps it in 4 bits
+            let level = pokemon
+                .dynamax_level
+                .unwrap_or(DEFAULT_DYNAMAX_LEVEL)
+                .min(DEFAULT_DYNAMAX_LEVEL);
+            if pokemon.gigantamax { GIGANTAMAX_BIT | level } else { level }
+        },
+        Generation::Gen7 => {
+            l.get(EntryKind::Tera, type_name(pokemon.hidden_power)) as u8
+        },
     }
 }
 
-fn decode_moves(table: &Vec<String>, moves_bin: &Vec<u16>) -> Vec<String> {
-    let mut moves: Vec<String> = Vec::new();
-    for m in moves_bin {
-        moves.push(binary_to_element(table, (*m).into()));
+fn decode_gen_slot(dex: &Dex, slot: u8, pokemon: &mut Pokemon) {
+    match dex.generation {
+        Generation::Gen9 => {
+            pokemon.tera = binary_to_typed(&dex.tables.teras, slot.into());
+        },
+        Generation::Gen8 => {
+            let level = slot & DYNAMAX_LEVEL_MASK;
+            if level != DEFAULT_DYNAMAX_LEVEL {
+                pokemon.dynamax_level = Some(level);
+            }
+            pokemon.gigantamax = slot & GIGANTAMAX_BIT != 0;
+        },
+        Generation::Gen7 => {
+            pokemon.hidden_power = binary_to_typed(&dex.tables.teras, slot.into());
+        },
     }
-    moves
 }
 
-fn decode_tvs(tvs: &TvBin, ifiv: bool) -> Tv {
-    Tv {
-        ifiv,
-        hp:     tvs.hp.to_string(),
-        atk:    tvs.atk.to_string(),
-        def:    tvs.def.to_string(),
-        spa:    tvs.spa.to_string(),
-        spd:    tvs.spd.to_string(),
-        spe:    tvs.spe.to_string(),
+// our chill o(1) lookup?
+// clone is fine, an id we don't know about decodes as empty
+fn binary_to_element(table: &HashMap<DexId, String>, id: DexId) -> String {
+    table.get(&id).cloned().unwrap_or_default()
+}
+
+// natures and types, the empty entry (or a name we can't read) is None
+fn binary_to_typed<T: FromStr>(
+    table: &HashMap<DexId, String>,
+    id: DexId
+) -> Option<T> {
+    binary_to_element(table, id).parse().ok()
+}
+
+fn gender_to_binary(gender: Option<Gender>) -> u8 {
+    // male, female or genderless
+    match gender {
+        Some(Gender::Male) => 0,
+        Some(Gender::Female) => 1,
+        None => 2,
     }
-} 
+}
 
-fn encode_tvs(tvs: &Tv, ifiv: bool) -> TvBin {
-    TvBin {
-        hp:     small_to_u8(&tvs.hp, ifiv),
-        atk:    small_to_u8(&tvs.atk, ifiv),
-        def:    small_to_u8(&tvs.def, ifiv),
-        spa:    small_to_u8(&tvs.spa, ifiv),
-        spd:    small_to_u8(&tvs.spd, ifiv),
-        spe:    small_to_u8(&tvs.spe, ifiv),
+fn binary_to_gender(gender: u8) -> Option<Gender> {
+    match gender {
+        0 => Some(Gender::Male),
+        1 => Some(Gender::Female),
+        _ => None,
     }
 }
 
-fn encode_moves(
-    moves_map: &HashMap<String, usize>, 
-    moves: &Vec<String>
-) -> Vec<u16> {
-    moves
+// we use the .into() to convert to DexId
+// the dex decides which generation the tera slot is read as
+pub fn pokebin_to_string(dex: &Dex, pbin: &PokemonBin) -> Pokemon {
+    let tables = &dex.tables;
+    let mut pokemon = Pokemon {
+        nickname:   pbin.nickname.clone(),
+        name:       binary_to_element(&tables.names, pbin.name),
+        gender:     binary_to_gender(pbin.gender),
+        item:       binary_to_element(&tables.items, pbin.item),
+        ability:    binary_to_element(&tables.abilities, pbin.ability),
+        level:      if pbin.level == 0 { None } else { Some(pbin.level) },
+        shiny:      pbin.shiny,
+        happiness:  pbin.happiness,
+        evs:        pbin.evs,
+        nature:     binary_to_typed(&tables.natures, pbin.nature.into()),
+        ivs:        pbin.ivs,
+        moves:      decode_moves(&tables.moves, &pbin.moves),
+        ..Default::default()
+    };
+    decode_gen_slot(dex, pbin.tera, &mut pokemon);
+    pokemon
+}
+
+// empty slots are left out, same as a paste with fewer moves
+fn decode_moves(
+    table: &HashMap<DexId, String>,
+    moves_bin: &[u16; MAX_MOVES]
+) -> ArrayVec<MoveId, MAX_MOVES> {
+    moves_bin
         .iter()
-        .map(|m| element_to_binary(moves_map, m) as u16)
+        .filter_map(|m| binary_to_element(table, *m).parse().ok())
         .collect()
 }
 
-pub fn encoded_pokemon(maps: &Maps, pokemon: &Pokemon) -> PokemonBin {
+fn encode_moves(lookup: &mut Lookup, moves: &[MoveId]) -> [u16; MAX_MOVES] {
+    let mut moves_bin = [0; MAX_MOVES];
+    for (slot, m) in moves_bin.iter_mut().zip(moves) {
+        *slot = lookup.get(EntryKind::Move, m.name());
+    }
+    moves_bin
+}
+
+fn nature_name(nature: Option<Nature>) -> &'static str {
+    nature.map(Nature::name).unwrap_or_default()
+}
+
+// unknown names are encoded as 0 (empty) and pushed onto unknown
+fn encode_pokemon_collecting(
+    dex: &Dex, 
+    pokemon: &Pokemon,
+    block_index: usize,
+    unknown: &mut Vec<EncodeError>,
+) -> PokemonBin {
+    // a slot the dex can't resolve (no species data for it) is left empty,
+    // the rest of the set is still worth packing
+    let ability = match dex.resolve_ability(&pokemon.name, &pokemon.ability) {
+        slot if ABILITY_SLOTS.contains(&slot) => "",
+        ability => ability,
+    };
+    let mut l = Lookup { dex, block_index, unknown };
     PokemonBin {
-        name:       element_to_binary(&maps.names, &pokemon.name) as u16,
-        gender:     gender_to_binary(&pokemon.gender) as u8,
-        item:       element_to_binary(&maps.items, &pokemon.item) as u16,
-        ability:    element_to_binary(&maps.abilities, &pokemon.ability) as u16,
-        level:      small_to_u8(&pokemon.level, false) as u8,
-        shiny:      pokemon.shiny.to_lowercase() == "yes",
-        tera:       element_to_binary(&maps.teras, &pokemon.tera) as u8,
-        evs:        encode_tvs(&pokemon.evs, false),
-        nature:     element_to_binary(&maps.natures, &pokemon.nature) as u8,
-        ivs:        encode_tvs(&pokemon.ivs, true),
-        moves:      encode_moves(&maps.moves, &pokemon.moves),
+        name:       l.get(EntryKind::Species, &pokemon.name),
+        gender:     gender_to_binary(pokemon.gender),
+        item:       l.get(EntryKind::Item, &pokemon.item),
+        ability:    l.get(EntryKind::Ability, ability),
+        level:      pokemon.level.unwrap_or(0),
+        shiny:      pokemon.shiny,
+        tera:       encode_gen_slot(&mut l, pokemon),
+        evs:        pokemon.evs,
+        nature:     l.get(EntryKind::Nature, nature_name(pokemon.nature)) as u8,
+        ivs:        pokemon.ivs,
+        moves:      encode_moves(&mut l, &pokemon.moves),
+        nickname:   pokemon.nickname.clone(),
+        // 255 is the same as not saying, showdown's packer drops it too
+        happiness:  pokemon.happiness.filter(|&h| h != MAX_HAPPINESS),
+    }
+}
+
+// fails on the first name that isn't in the dex
+pub fn encoded_pokemon(
+    dex: &Dex, 
+    pokemon: &Pokemon,
+    block_index: usize,
+) -> Result<PokemonBin, EncodeError> {
+    let mut unknown = Vec::new();
+    let pbin = encode_pokemon_collecting(dex, pokemon, block_index, &mut unknown);
+    match unknown.into_iter().next() {
+        Some(err) => Err(err),
+        None => Ok(pbin),
     }
 }
 
 pub fn encode_all_pokemon(
-    maps: &Maps, 
+    dex: &Dex, 
     pokemons: Vec<Pokemon>
-) -> Vec<PokemonBin> {
+) -> Result<Vec<PokemonBin>, EncodeError> {
         pokemons
             .iter()
-            .map(|p| encoded_pokemon(&maps, p))
+            .enumerate()
+            .map(|(i, p)| encoded_pokemon(dex, p, i))
             .collect()
 }
 
+// lenient mode, always encodes everything
+// unknown names become empty and are handed back as warnings
+pub fn encode_all_pokemon_lenient(
+    dex: &Dex, 
+    pokemons: Vec<Pokemon>
+) -> (Vec<PokemonBin>, Vec<EncodeError>) {
+    let mut warnings = Vec::new();
+    let pokemon_bin = pokemons
+        .iter()
+        .enumerate()
+        .map(|(i, p)| encode_pokemon_collecting(dex, p, i, &mut warnings))
+        .collect();
+    (pokemon_bin, warnings)
+}
+
+// the container keeps the count, format and name lengths in a byte each
+fn check_team_limits(
+    count: usize,
+    format: &str,
+    name: &str
+) -> Result<(), EncodeError> {
+    let limit = u8::MAX as usize;
+    if count > limit {
+        return Err(EncodeError::TooManyPokemon { count });
+    }
+    if format.len() > limit {
+        return Err(EncodeError::MetadataTooLong {
+            field: "format",
+            len: format.len(),
+        });
+    }
+    if name.len() > limit {
+        return Err(EncodeError::MetadataTooLong {
+            field: "team name",
+            len: name.len(),
+        });
+    }
+    Ok(())
+}
+
+// the container only has a name, so the folder goes in front of it
+// the same "Folder/Name" path showdown writes in its backups
+fn team_path(team: &Team) -> String {
+    if team.folder.is_empty() {
+        team.name.clone()
+    } else {
+        format!("{}/{}", team.folder, team.name)
+    }
+}
+
+// the dex should be the one for team.generation, that's what gets recorded
+// formats are stored as showdown ids, "[Gen 9] OU" -> "gen9ou"
+pub fn encode_team(dex: &Dex, team: Team) -> Result<TeamBin, EncodeError> {
+    let format = dex::to_id(&team.format);
+    let name = team_path(&team);
+    check_team_limits(team.pokemon.len(), &format, &name)?;
+    Ok(TeamBin {
+        generation: dex.generation,
+        format,
+        name,
+        pokemon:    encode_all_pokemon(dex, team.pokemon)?,
+    })
+}
+
+// same as encode_all_pokemon_lenient, the limits are still hard errors
+pub fn encode_team_lenient(
+    dex: &Dex,
+    team: Team
+) -> Result<(TeamBin, Vec<EncodeError>), EncodeError> {
+    let format = dex::to_id(&team.format);
+    let name = team_path(&team);
+    check_team_limits(team.pokemon.len(), &format, &name)?;
+    let (pokemon, warnings) = encode_all_pokemon_lenient(dex, team.pokemon);
+    let team_bin = TeamBin {
+        generation: dex.generation,
+        format,
+        name,
+        pokemon,
+    };
+    Ok((team_bin, warnings))
+}
+
+pub fn decode_team(dex: &Dex, team_bin: &TeamBin) -> Team {
+    let (folder, name) = parser::split_team_path(&team_bin.name);
+    Team {
+        generation: team_bin.generation,
+        format:     team_bin.format.clone(),
+        folder,
+        name,
+        pokemon:    team_bin
+            .pokemon
+            .iter()
+            .map(|p| pokebin_to_string(dex, p))
+            .collect(),
+    }
+}
+
 
 // gonna treat these kind of like unit tests
 // maybe I should combine each pair
@@ -158,7 +373,16 @@ mod tests {
     #[test]
     fn test_element_to_binary() {
         let dex = crate::get_dex();
-        assert_eq!(element_to_binary(&dex.maps.names, "bulbasaur"), 0);
+        assert_eq!(element_to_binary(&dex.maps.names, "bulbasaur"), Some(0));
+        assert_eq!(element_to_binary(&dex.maps.names, "gholdngo"), None);
+        assert_eq!(
+            element_to_binary(&dex.maps.names, "Ursaluna Bloodmoon"),
+            element_to_binary(&dex.maps.names, "ursaluna-bloodmoon")
+        );
+        assert_eq!(
+            element_to_binary(&dex.maps.moves, "uturn"),
+            element_to_binary(&dex.maps.moves, "U-turn")
+        );
     }
 
     #[test]
@@ -169,28 +393,205 @@ mod tests {
 
     #[test]
     fn test_gender_to_binary() {
-        assert_eq!(gender_to_binary("m"), 0);
-        assert_eq!(gender_to_binary("f"), 1);
-        assert_eq!(gender_to_binary("a"), 2);
+        assert_eq!(gender_to_binary(Some(Gender::Male)), 0);
+        assert_eq!(gender_to_binary(Some(Gender::Female)), 1);
+        assert_eq!(gender_to_binary(None), 2);
     }
 
     #[test]
     fn test_binary_to_gender() {
-        assert_eq!(binary_to_gender(0), "m");
-        assert_eq!(binary_to_gender(1), "f");
-        assert_eq!(binary_to_gender(2), "");
+        assert_eq!(binary_to_gender(0), Some(Gender::Male));
+        assert_eq!(binary_to_gender(1), Some(Gender::Female));
+        assert_eq!(binary_to_gender(2), None);
+    }
+
+    #[test]
+    fn test_typed_fields_roundtrip() {
+        let dex = crate::get_dex();
+        let mut pokemon = sample_pokemon("gholdengo", "choice specs");
+        pokemon.level = Some(50);
+        pokemon.nature = Some(Nature::Timid);
+        pokemon.tera = Some(Type::Steel);
+        pokemon.evs.spa = 252;
+        pokemon.ivs.atk = 0;
+        pokemon.moves.push("Make It Rain".parse().unwrap());
+        let pbin = encoded_pokemon(dex, &pokemon, 0).unwrap();
+        assert_eq!(pbin.moves[1..], [0, 0, 0]);
+        let decoded = pokebin_to_string(dex, &pbin);
+        assert_eq!(decoded.level, Some(50));
+        assert_eq!(decoded.nature, Some(Nature::Timid));
+        assert_eq!(decoded.tera, Some(Type::Steel));
+        assert_eq!(decoded.evs, pokemon.evs);
+        assert_eq!(decoded.ivs, pokemon.ivs);
+        assert_eq!(decoded.moves.as_slice(), ["Make It Rain"]);
+    }
+
+    fn sample_pokemon(name: &str, item: &str) -> Pokemon {
+        Pokemon {
+            name: name.into(),
+            item: item.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_unknown_entry_is_an_error() {
+        let dex = crate::get_dex();
+        let team = vec![
+            sample_pokemon("gholdengo", "choice specs"),
+            sample_pokemon("gholdngo", "choice specs"),
+        ];
+        assert_eq!(
+            encode_all_pokemon(dex, team),
+            Err(EncodeError::UnknownEntry {
+                kind: EntryKind::Species,
+                value: "gholdngo".into(),
+                block_index: 1,
+                suggestions: vec!["Gholdengo".into()],
+            })
+        );
+    }
+
+    #[test]
+    fn test_lenient_collects_warnings() {
+        let dex = crate::get_dex();
+        let team = vec![
+            sample_pokemon("gholdngo", "life orbe"),
+            sample_pokemon("gholdengo", ""),
+        ];
+        let (pokemon_bin, warnings) = encode_all_pokemon_lenient(dex, team);
+        assert_eq!(pokemon_bin.len(), 2);
+        assert_eq!(pokemon_bin[0].item, 0);
+        assert_eq!(warnings.len(), 2);
+        assert!(matches!(
+            &warnings[1],
+            EncodeError::UnknownEntry { kind: EntryKind::Item, .. }
+        ));
+    }
+
+    // the client writes each ability as its slot, "" is slot 0
+    const SLOT_ABILITIES: &str = "\
+Incineroar||sitrusberry|H|fakeout,knockoff,flareblitz,partingshot|Careful|\
+252,4,,,252,|F|||50|,,,,,Ghost]\
+Rillaboom||assaultvest||fakeout,woodhammer,grassyglide,uturn|Adamant|\
+252,116,,,4,132|M|||50|,,,,,Fire";
+
+    #[test]
+    fn test_packed_ability_slots() {
+        let mut dex = Dex::build(Generation::Gen9).unwrap();
+        let names = &dex.maps.names;
+        let species = format!(
+            "{}\t95/115/90/80/90/60\tFire/Dark\tBlaze//Intimidate\t830\t{0}\n\
+             {}\t100/125/90/60/70/85\tGrass\tOvergrow//Grassy Surge\t900\t{1}\n",
+            names["incineroar"], names["rillaboom"]
+        );
+        dex.species = crate::species::parse_species(&species, &dex.maps.abilities, true)
+            .unwrap();
+
+        let team = parser::parse_packed_team(SLOT_ABILITIES).unwrap();
+        let decoded: Vec<Pokemon> = encode_all_pokemon(&dex, team)
+            .unwrap()
+            .iter()
+            .map(|pbin| pokebin_to_string(&dex, pbin))
+            .collect();
+        assert_eq!(decoded[0].ability, "Intimidate");
+        assert_eq!(decoded[1].ability, "Overgrow");
+
+        // without the species' abilities the slots are left empty,
+        // not an error that throws the whole team away
+        let dex = gen_dex(Generation::Gen9);
+        let team = parser::parse_packed_team(SLOT_ABILITIES).unwrap();
+        let pokemon_bin = encode_all_pokemon(&dex, team).unwrap();
+        assert_eq!(pokemon_bin[0].ability, 0);
+        assert_eq!(pokemon_bin[1].ability, 0);
+        assert_eq!(pokebin_to_string(&dex, &pokemon_bin[1]).ability, "");
+    }
+
+    fn gen_dex(generation: Generation) -> Dex {
+        Dex::build(generation).unwrap()
     }
-    
+
     #[test]
-    fn test_small_to_u8() {
-        assert_eq!(small_to_u8("252", false), 252);
-        assert_eq!(small_to_u8("", false), 0);
-        assert_eq!(small_to_u8("", true), 31);
+    fn test_gen_slot_roundtrip() {
+        let gen8 = gen_dex(Generation::Gen8);
+        let mut pokemon = sample_pokemon("charizard-gmax", "heavy-duty boots");
+        pokemon.dynamax_level = Some(5);
+        pokemon.gigantamax = true;
+        let pbin = encoded_pokemon(&gen8, &pokemon, 0).unwrap();
+        assert_eq!(pbin.tera, GIGANTAMAX_BIT | 5);
+        let decoded = pokebin_to_string(&gen8, &pbin);
+        assert_eq!(decoded.dynamax_level, Some(5));
+        assert!(decoded.gigantamax);
+        assert_eq!(decoded.tera, None);
+
+        let gen7 = gen_dex(Generation::Gen7);
+        let mut pokemon = sample_pokemon("magnezone", "firium z");
+        pokemon.hidden_power = Some(Type::Fire);
+        let pbin = encoded_pokemon(&gen7, &pokemon, 0).unwrap();
+        assert_eq!(pokebin_to_string(&gen7, &pbin).hidden_power, Some(Type::Fire));
+    }
+
+    #[test]
+    fn test_fields_from_other_generations_are_errors() {
+        let gen8 = gen_dex(Generation::Gen8);
+        let mut pokemon = sample_pokemon("corviknight", "leftovers");
+        pokemon.tera = Some(Type::Fire);
+        assert_eq!(
+            encoded_pokemon(&gen8, &pokemon, 2),
+            Err(EncodeError::NotInGeneration {
+                field: "Tera Type",
+                generation: Generation::Gen8,
+                block_index: 2,
+            })
+        );
+        // and things that didn't exist yet are unknown
+        let gen7 = gen_dex(Generation::Gen7);
+        assert!(matches!(
+            encoded_pokemon(&gen7, &pokemon, 0),
+            Err(EncodeError::UnknownEntry { kind: EntryKind::Species, .. })
+        ));
+    }
+
+    #[test]
+    fn test_team_roundtrip() {
+        let dex = crate::get_dex();
+        let team = Team {
+            generation: Generation::Gen9,
+            format: "[Gen 9] VGC 2024 Reg G".into(),
+            folder: "VGC/Reg G".into(),
+            name: "Sun Room".into(),
+            pokemon: vec![sample_pokemon("gholdengo", "choice specs")],
+        };
+        let team_bin = encode_team(dex, team).unwrap();
+        assert_eq!(team_bin.format, "gen9vgc2024regg");
+        assert_eq!(team_bin.name, "VGC/Reg G/Sun Room");
+        let decoded = decode_team(dex, &team_bin);
+        assert_eq!(decoded.folder, "VGC/Reg G");
+        assert_eq!(decoded.name, "Sun Room");
+        assert_eq!(decoded.pokemon[0].name, "Gholdengo");
+    }
+
+    #[test]
+    fn test_team_limits() {
+        let dex = crate::get_dex();
+        let team = Team {
+            pokemon: vec![sample_pokemon("pikachu", ""); 256],
+            ..Default::default()
+        };
+        assert_eq!(
+            encode_team(dex, team),
+            Err(EncodeError::TooManyPokemon { count: 256 })
+        );
+        let team = Team { name: "a".repeat(256), ..Default::default() };
+        assert!(matches!(
+            encode_team(dex, team),
+            Err(EncodeError::MetadataTooLong { field: "team name", .. })
+        ));
     }
 
 /*
     pub fn pokebin_to_string(tables: &Tables, pbin: &PokemonBin) -> Pokemon {
-    fn decode_moves(table: &Vec<String>, moves_bin: &Vec<u16>) -> Vec<String> {
+    fn decode_moves(table: &HashMap<DexId, String>, moves_bin: &Vec<u16>) -> Vec<String> {
     fn decode_tvs(tvs: &TvBin, ifiv: bool) -> Tv {
     fn encode_tvs(tvs: &Tv, ifiv: bool) -> TvBin {
     fn encode_moves(
@@ -0,0 +1,470 @@
+/*
+* damage.rs
+*
+* a gen 9 damage calc between two sets, the same steps and rounding as
+* showdown's calc so the numbers line up
+*
+*   base = (2 * level / 5 + 2) * power * attack / defense / 50 + 2
+*
+* then spread, weather, crit, the random roll (85% to 100%), stab, type
+* effectiveness and screens, each rounded on its own
+* modifiers are out of 4096 like the games, 6144 is 1.5x and so on
+* items, abilities and stat boosts aren't taken into account yet
+*/
+
+use std::fmt;
+
+use arrayvec::ArrayVec;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    dex::{self, Dex},
+    error::CalcError,
+    model::{Stats, Type},
+    moves::Category,
+    parser::Pokemon,
+    stats::{self, apply_mod},
+};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPESCRIPT: &str = r#"
+export interface Field {
+    weather?: "sun" | "rain" | "sand" | "snow" | null;
+    terrain?: "electric" | "grassy" | "psychic" | "misty" | null;
+    doubles?: boolean;
+    reflect?: boolean;
+    lightScreen?: boolean;
+    auroraVeil?: boolean;
+    critical?: boolean;
+    attackerTera?: boolean;
+    defenderTera?: boolean;
+}
+
+export interface KoChance {
+    hits: number;
+    chance: number;
+}
+
+export interface DamageResult {
+    rolls: number[];
+    defenderHp: number;
+    minPercent: number;
+    maxPercent: number;
+    ko: KoChance | null;
+}
+"#;
+
+// every roll from 85 to 100
+pub const ROLLS: usize = 16;
+// we stop looking for a ko after this many hits
+const MAX_KO_HITS: u8 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Weather {
+    Sun,
+    Rain,
+    Sand,
+    Snow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Terrain {
+    Electric,
+    Grassy,
+    Psychic,
+    Misty,
+}
+
+// everything that isn't the two sets, all off by default
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Field {
+    pub weather:        Option<Weather>,
+    pub terrain:        Option<Terrain>,
+    // spread moves do 0.75x and screens are weaker in doubles
+    pub doubles:        bool,
+    // the defender's side
+    pub reflect:        bool,
+    pub light_screen:   bool,
+    pub aurora_veil:    bool,
+    pub critical:       bool,
+    // whether each side has terastallized into its set's tera type
+    pub attacker_tera:  bool,
+    pub defender_tera:  bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct KoChance {
+    // 1 for an ohko, 2 for a 2hko...
+    pub hits:   u8,
+    // 0 to 1, 1 is guaranteed
+    pub chance: f32,
+}
+
+impl fmt::Display for KoChance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hits = match self.hits {
+            1 => "OHKO".to_string(),
+            n => format!("{}HKO", n),
+        };
+        if self.chance >= 1.0 {
+            write!(f, "guaranteed {}", hits)
+        } else {
+            write!(f, "{:.1}% chance to {}", self.chance * 100.0, hits)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DamageResult {
+    // lowest roll first
+    pub rolls:          [u16; ROLLS],
+    pub defender_hp:    u16,
+    pub min_percent:    f32,
+    pub max_percent:    f32,
+    // None when four hits can't do it
+    pub ko:             Option<KoChance>,
+}
+
+impl DamageResult {
+    pub fn min(&self) -> u16 {
+        self.rolls[0]
+    }
+
+    pub fn max(&self) -> u16 {
+        self.rolls[ROLLS - 1]
+    }
+}
+
+// 138-164 (68.3 - 81.2%) -- guaranteed 2HKO
+impl fmt::Display for DamageResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}-{} ({:.1} - {:.1}%)",
+            self.min(),
+            self.max(),
+            self.min_percent,
+            self.max_percent
+        )?;
+        match self.ko {
+            Some(ko) => write!(f, " -- {}", ko),
+            None => Ok(()),
+        }
+    }
+}
+
+// one side of the calc, what the formula needs to know about a set
+struct Side {
+    stats:  Stats<u16>,
+    types:  ArrayVec<Type, 2>,
+    level:  u32,
+    // only set when this side has terastallized
+    tera:   Option<Type>,
+}
+
+impl Side {
+    fn new(dex: &Dex, pokemon: &Pokemon, terastallized: bool) -> Result<Self, CalcError> {
+        let unknown = || CalcError::UnknownSpecies { name: pokemon.name.clone() };
+        let species = dex.species_data(&pokemon.name).ok_or_else(unknown)?;
+        Ok(Side {
+            stats:  stats::pokemon_stats(dex, pokemon).ok_or_else(unknown)?,
+            types:  species.types.clone(),
+            level:  pokemon.level.unwrap_or(crate::model::MAX_LEVEL) as u32,
+            tera:   pokemon.tera.filter(|_| terastallized),
+        })
+    }
+
+    // a tera type replaces the defensive types, stellar doesn't
+    fn defensive_types(&self) -> ArrayVec<Type, 2> {
+        match self.tera {
+            Some(tera) if tera != Type::Stellar => [tera].into_iter().collect(),
+            _ => self.types.clone(),
+        }
+    }
+
+    // ignores levitate and air balloon
+    fn grounded(&self) -> bool {
+        !self.defensive_types().contains(&Type::Flying)
+    }
+}
+
+pub fn calc_damage(
+    dex: &Dex,
+    attacker: &Pokemon,
+    defender: &Pokemon,
+    move_name: &str,
+    field: &Field
+) -> Result<DamageResult, CalcError> {
+    let data = dex
+        .move_data(move_name)
+        .ok_or_else(|| CalcError::UnknownMove { name: move_name.to_string() })?;
+    if !data.is_damaging() {
+        return Err(CalcError::StatusMove { name: move_name.to_string() });
+    }
+    if data.base_power == 0 {
+        return Err(CalcError::VariablePower { name: move_name.to_string() });
+    }
+    let attacker = Side::new(dex, attacker, field.attacker_tera)?;
+    let defender = Side::new(dex, defender, field.defender_tera)?;
+
+    // tera blast takes the tera type, and hits off the higher attack
+    let tera_blast = dex::to_id(move_name) == "terablast";
+    let (move_type, category) = match attacker.tera {
+        Some(tera) if tera_blast => {
+            let physical = attacker.stats.atk > attacker.stats.spa;
+            (tera, if physical { Category::Physical } else { Category::Special })
+        },
+        _ => (data.move_type, data.category),
+    };
+    let physical = category == Category::Physical;
+    let defender_types = defender.defensive_types();
+
+    // weak moves of the tera type are raised to 60
+    // showdown skips priority and multi hit moves, we don't know those
+    let mut power = data.base_power as u32;
+    if attacker.tera == Some(move_type) && power < 60 {
+        power = 60;
+    }
+    let terrain_boost = match field.terrain {
+        Some(Terrain::Electric) => move_type == Type::Electric,
+        Some(Terrain::Grassy) => move_type == Type::Grass,
+        Some(Terrain::Psychic) => move_type == Type::Psychic,
+        _ => false,
+    };
+    if terrain_boost && attacker.grounded() {
+        power = apply_mod(power, 5325);
+    }
+    if field.terrain == Some(Terrain::Misty)
+        && move_type == Type::Dragon
+        && defender.grounded()
+    {
+        power = apply_mod(power, 2048);
+    }
+
+    let attack = if physical { attacker.stats.atk } else { attacker.stats.spa } as u32;
+    let mut defense = if physical { defender.stats.def } else { defender.stats.spd } as u32;
+    let weather_defense = match field.weather {
+        Some(Weather::Sand) => !physical && defender_types.contains(&Type::Rock),
+        Some(Weather::Snow) => physical && defender_types.contains(&Type::Ice),
+        _ => false,
+    };
+    if weather_defense {
+        defense = apply_mod(defense, 6144);
+    }
+
+    let mut base = (2 * attacker.level / 5 + 2) * power * attack / defense.max(1) / 50 + 2;
+    if field.doubles && data.spread {
+        base = apply_mod(base, 3072);
+    }
+    let weather_mod = match (field.weather, move_type) {
+        (Some(Weather::Sun), Type::Fire) | (Some(Weather::Rain), Type::Water) => 6144,
+        (Some(Weather::Sun), Type::Water) | (Some(Weather::Rain), Type::Fire) => 2048,
+        _ => 4096,
+    };
+    base = apply_mod(base, weather_mod);
+    if field.critical {
+        base = base * 3 / 2;
+    }
+
+    let stab = stab_mod(&attacker, move_type);
+    let effectiveness = dex.type_chart.effectiveness(move_type, &defender_types);
+    let screen = !field.critical && (field.aurora_veil || match category {
+        Category::Physical => field.reflect,
+        _ => field.light_screen,
+    });
+    let screen_mod = match (screen, field.doubles) {
+        (false, _) => 4096,
+        (true, false) => 2048,
+        (true, true) => 2732,
+    };
+
+    let mut rolls = [0; ROLLS];
+    for (i, roll) in rolls.iter_mut().enumerate() {
+        let mut damage = base * (85 + i as u32) / 100;
+        damage = apply_mod(damage, stab);
+        damage = (damage as f32 * effectiveness) as u32;
+        damage = apply_mod(damage, screen_mod);
+        if effectiveness > 0.0 {
+            damage = damage.max(1);
+        }
+        *roll = damage.min(u16::MAX as u32) as u16;
+    }
+
+    let hp = defender.stats.hp;
+    let percent = |damage: u16| damage as f32 * 100.0 / hp as f32;
+    Ok(DamageResult {
+        rolls,
+        defender_hp: hp,
+        min_percent: percent(rolls[0]),
+        max_percent: percent(rolls[ROLLS - 1]),
+        ko: ko_chance(&rolls, hp),
+    })
+}
+
+// 1.5x for a move of one of its types, 2x if it also terastallized into it
+// stellar gives 2x to its own types and 1.2x to everything else
+fn stab_mod(attacker: &Side, move_type: Type) -> u32 {
+    let original = attacker.types.contains(&move_type);
+    match attacker.tera {
+        Some(Type::Stellar) if original => 8192,
+        Some(Type::Stellar) => 4915,
+        Some(tera) if tera == move_type && original => 8192,
+        Some(tera) if tera == move_type => 6144,
+        _ if original => 6144,
+        _ => 4096,
+    }
+}
+
+// the fewest hits with any chance to ko, every roll is as likely
+// chances[d] is how likely it is to have done exactly d damage so far,
+// with everything at or above hp counted in chances[hp]
+pub fn ko_chance(rolls: &[u16; ROLLS], hp: u16) -> Option<KoChance> {
+    let hp = hp as usize;
+    let mut chances = vec![0.0f64; hp + 1];
+    chances[0] = 1.0;
+    for hits in 1..=MAX_KO_HITS {
+        let mut next = vec![0.0f64; hp + 1];
+        for (done, &chance) in chances.iter().enumerate().filter(|(_, &c)| c > 0.0) {
+            for &roll in rolls {
+                let total = if done == hp { hp } else { (done + roll as usize).min(hp) };
+                next[total] += chance / ROLLS as f64;
+            }
+        }
+        chances = next;
+        if chances[hp] > 0.0 {
+            return Some(KoChance { hits, chance: chances[hp] as f32 });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dex::Generation, moves, parser, species};
+
+    fn test_dex() -> Dex {
+        let mut dex = Dex::build(Generation::Gen9).unwrap();
+        let names = &dex.maps.names;
+        let species = format!(
+            "{}\t95/115/90/80/90/60\tFire/Dark\tBlaze//Intimidate\t830\t{0}\n\
+             {}\t108/130/95/80/85/102\tDragon/Ground\tSand Veil//Rough Skin\t950\t{1}\n",
+            names["incineroar"], names["garchomp"]
+        );
+        dex.species = species::parse_species(&species, &dex.maps.abilities, true).unwrap();
+        let moves = &dex.maps.moves;
+        let move_data = format!(
+            "{}\tGround\tPhysical\t100\tallAdjacent\n{}\tDragon\tPhysical\t80\tnormal\n\
+             {}\tNormal\tStatus\t0\tself\n{}\tNormal\tSpecial\t80\tnormal\n\
+             {}\tFighting\tPhysical\t0\tnormal\n",
+            moves["earthquake"], moves["dragonclaw"], moves["protect"],
+            moves["terablast"], moves["lowkick"]
+        );
+        dex.move_data = moves::parse_moves(&move_data).unwrap();
+        dex
+    }
+
+    fn sets() -> Vec<Pokemon> {
+        let paste = "Garchomp\nLevel: 50\nTera Type: Ground\n\
+                     EVs: 252 Atk / 4 SpD / 252 Spe\nJolly Nature\n\n\
+                     Incineroar\nLevel: 50\nTera Type: Water\n\
+                     EVs: 252 HP / 4 Atk / 252 SpD\nCareful Nature\nIVs: 0 Spe";
+        parser::parse_pokepaste(paste.to_string()).unwrap()
+    }
+
+    // worked out by hand with the calc's formula and rounding:
+    // base 74, spread 55, then 46..55 from the roll, stab and 2x on top
+    #[test]
+    fn test_spread_earthquake() {
+        let dex = test_dex();
+        let team = sets();
+        let field = Field { doubles: true, ..Field::default() };
+        let result = calc_damage(&dex, &team[0], &team[1], "Earthquake", &field).unwrap();
+        assert_eq!(result.rolls, [
+            138, 140, 140, 144, 144, 146, 150, 150,
+            152, 152, 156, 156, 158, 158, 162, 164,
+        ]);
+        assert_eq!(result.defender_hp, 202);
+        assert_eq!(result.ko, Some(KoChance { hits: 2, chance: 1.0 }));
+        assert_eq!(result.to_string(), "138-164 (68.3 - 81.2%) -- guaranteed 2HKO");
+
+        // one target, so no spread penalty
+        let single = calc_damage(&dex, &team[0], &team[1], "Earthquake", &Field::default());
+        assert!(single.unwrap().min() > result.max());
+    }
+
+    // the same hit through the wasm export, against the shipped data
+    #[test]
+    #[ignore = "needs dex/species.txt and dex/movedata.txt, see dex_build"]
+    fn test_shipped_spread_earthquake() {
+        let team = sets();
+        let field = Field { doubles: true, ..Field::default() };
+        let result = calc_damage(crate::get_dex(), &team[0], &team[1], "Earthquake", &field)
+            .unwrap();
+        assert_eq!(result.to_string(), "138-164 (68.3 - 81.2%) -- guaranteed 2HKO");
+    }
+
+    #[test]
+    fn test_field_and_tera() {
+        let dex = test_dex();
+        let team = sets();
+        let plain = calc_damage(&dex, &team[0], &team[1], "Earthquake", &Field::default())
+            .unwrap();
+
+        // ground tera on a ground type is 2x stab instead of 1.5x
+        let tera = Field { attacker_tera: true, ..Field::default() };
+        let boosted = calc_damage(&dex, &team[0], &team[1], "Earthquake", &tera).unwrap();
+        assert!(boosted.min() > plain.min());
+
+        // water tera turns the weakness into a neutral hit
+        let tera = Field { defender_tera: true, ..Field::default() };
+        let neutral = calc_damage(&dex, &team[0], &team[1], "Earthquake", &tera).unwrap();
+        assert!(neutral.max() < plain.min());
+
+        let reflect = Field { reflect: true, ..Field::default() };
+        let screened = calc_damage(&dex, &team[0], &team[1], "Earthquake", &reflect).unwrap();
+        assert!(screened.max() <= plain.max() / 2 + 1);
+        let crit = Field { reflect: true, critical: true, ..Field::default() };
+        let crit = calc_damage(&dex, &team[0], &team[1], "Earthquake", &crit).unwrap();
+        assert!(crit.min() > plain.max());
+
+        // tera blast becomes ground and physical for garchomp
+        let tera = Field { attacker_tera: true, ..Field::default() };
+        let blast = calc_damage(&dex, &team[0], &team[1], "Tera Blast", &tera).unwrap();
+        assert!(blast.min() > 0);
+        assert!(blast.max() < boosted.min());
+    }
+
+    #[test]
+    fn test_calc_errors() {
+        let dex = test_dex();
+        let mut team = sets();
+        let calc = |team: &[Pokemon], name: &str| {
+            calc_damage(&dex, &team[0], &team[1], name, &Field::default())
+        };
+        assert!(matches!(calc(&team, "Protect"), Err(CalcError::StatusMove { .. })));
+        assert!(matches!(calc(&team, "Low Kick"), Err(CalcError::VariablePower { .. })));
+        assert!(matches!(calc(&team, "Thunderbolt"), Err(CalcError::UnknownMove { .. })));
+        team[1].name = "Pikachu".to_string();
+        assert!(matches!(calc(&team, "Earthquake"), Err(CalcError::UnknownSpecies { .. })));
+    }
+
+    #[test]
+    fn test_ko_chance() {
+        let mut rolls = [0; ROLLS];
+        for (i, roll) in rolls.iter_mut().enumerate() {
+            *roll = 85 + i as u16;
+        }
+        assert_eq!(ko_chance(&rolls, 85), Some(KoChance { hits: 1, chance: 1.0 }));
+        // the top 4 rolls
+        assert_eq!(ko_chance(&rolls, 97), Some(KoChance { hits: 1, chance: 0.25 }));
+        assert_eq!(ko_chance(&rolls, 101).unwrap().hits, 2);
+        assert_eq!(ko_chance(&rolls, 1000), None);
+        assert_eq!(KoChance { hits: 1, chance: 0.25 }.to_string(), "25.0% chance to OHKO");
+    }
+}
@@ -5,81 +5,329 @@
 * first we parse the .txt files from the dex folders into Vec<String>
 * then we turn those in HashMaps
 * this allows an o(1) lookup for both encoding and decoding the binary
+* every generation shares the same ids, older gens just see fewer entries
 */
 
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::{
+    error::DexError,
+    learnset,
+    moves::{self, MoveData},
+    species::{self, Species},
+    typechart::TypeChart,
+};
 
 // our ground truth for building the dex
-// all elements on a newline, simple to parser
+// one "ID<TAB>Name" entry per line, simple to parse
+// the ids are frozen, see build.rs and dex/ids.lock
 const NAMES:        &str = include_str!("../dex/names.txt");
 const ITEMS:        &str = include_str!("../dex/items.txt");
 const ABILITIES:    &str = include_str!("../dex/abilities.txt");
 const MOVES:        &str = include_str!("../dex/moves.txt");
 const NATURES:      &str = include_str!("../dex/natures.txt");
 const TERAS:        &str = include_str!("../dex/teras.txt");
+const SPECIES:      &str = include_str!("../dex/species.txt");
+const MOVE_DATA:    &str = include_str!("../dex/movedata.txt");
+const TYPE_CHART:   &str = include_str!("../dex/typechart.txt");
+// binary, see learnset.rs
+const LEARNSETS:    &[u8] = include_bytes!("../dex/learnsets.bin");
+
+// older or alternate spellings, one "Alias<TAB>Canonical" per line
+// these only help encoding, decoding always gives the canonical name
+// anything toID already handles ("Hidden Power [Fire]") doesn't need one
+const ALIAS_NAMES:  &str = include_str!("../dex/aliases/names.txt");
+const ALIAS_ITEMS:  &str = include_str!("../dex/aliases/items.txt");
+const ALIAS_MOVES:  &str = include_str!("../dex/aliases/moves.txt");
+
+// which ids existed in the older generations, same "ID<TAB>Name" lines
+// these are subsets of the files above, so an id means the same thing
+// in every gen and only the lookups change
+// natures and tera types are the same list in every gen
+struct GenFiles {
+    names:      &'static str,
+    items:      &'static str,
+    abilities:  &'static str,
+    moves:      &'static str,
+}
+
+const GEN7: GenFiles = GenFiles {
+    names:      include_str!("../dex/gen7/names.txt"),
+    items:      include_str!("../dex/gen7/items.txt"),
+    abilities:  include_str!("../dex/gen7/abilities.txt"),
+    moves:      include_str!("../dex/gen7/moves.txt"),
+};
+
+const GEN8: GenFiles = GenFiles {
+    names:      include_str!("../dex/gen8/names.txt"),
+    items:      include_str!("../dex/gen8/items.txt"),
+    abilities:  include_str!("../dex/gen8/abilities.txt"),
+    moves:      include_str!("../dex/gen8/moves.txt"),
+};
+
+// bump this whenever entries are appended to the files above
+// packed data records which revision it was built against
+// 2: empty tera type entry
+pub const DEX_REVISION: u16 = 2;
+
+// the number we store in the binary pack for any dex entry
+// these never change meaning once published, new entries get new ids
+pub type DexId = u16;
+
+// the generations we can pack, a team is always packed against one
+// the number is what goes into the envelope header
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Generation {
+    Gen7 = 7,
+    Gen8 = 8,
+    #[default]
+    Gen9 = 9,
+}
+
+impl Generation {
+    pub const ALL: [Generation; 3] = [
+        Generation::Gen7,
+        Generation::Gen8,
+        Generation::Gen9,
+    ];
+
+    pub fn number(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_number(number: u8) -> Option<Self> {
+        Generation::ALL.into_iter().find(|g| g.number() == number)
+    }
+
+    // showdown format ids start with the generation, "gen8ou" -> Gen 8
+    // None if there's no gen prefix, an unsupported one is an error
+    pub fn from_format(format: &str) -> Option<Result<Self, DexError>> {
+        let id = to_id(format);
+        let digits: String = id
+            .strip_prefix("gen")?
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        let number: u8 = digits.parse().ok()?;
+        Some(Generation::try_from(number))
+    }
+
+    // gen 9 is the full dex, nothing to filter
+    fn files(self) -> Option<&'static GenFiles> {
+        match self {
+            Generation::Gen7 => Some(&GEN7),
+            Generation::Gen8 => Some(&GEN8),
+            Generation::Gen9 => None,
+        }
+    }
+}
+
+impl TryFrom<u8> for Generation {
+    type Error = DexError;
+
+    fn try_from(number: u8) -> Result<Self, Self::Error> {
+        Generation::from_number(number)
+            .ok_or(DexError::UnsupportedGeneration { generation: number })
+    }
+}
+
+impl fmt::Display for Generation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Gen {}", self.number())
+    }
+}
+
+// which dex file an entry belongs to, mostly for error messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Species,
+    Item,
+    Ability,
+    Move,
+    Nature,
+    Tera,
+}
+
+impl fmt::Display for EntryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            EntryKind::Species  => "species",
+            EntryKind::Item     => "item",
+            EntryKind::Ability  => "ability",
+            EntryKind::Move     => "move",
+            EntryKind::Nature   => "nature",
+            EntryKind::Tera     => "tera type",
+        };
+        write!(f, "{}", s)
+    }
+}
 
 /*
 our dex struct contains two data structures
-Tables: containing array/vector types for o(1) decoding
-Maps: containing hashmaps for o(1) encoding
-using the vec for encoding would be o(n) on lookups
+Tables: id -> name, for o(1) decoding
+Maps: name -> id, for o(1) encoding
 
 illustration:
 
-table:
-[Bulbasaur, Ivysaur, Venusaur, etc]
-    0         1         2       3
-simple index access
+dex/names.txt:
+0   Bulbasaur
+1   Ivysaur
+2   Venusaur
 
+table:
+[(0, Bulbasaur), (1, Ivysaur), (2, Venusaur)]
 map:
-(key, value)
 [(bulbasaur, 0), (ivysaur, 1), (venusaur, 2)]
-the value is what we want to encode in our binary pack
+keys go through showdown's toID so "Mind's Eye", "minds eye" and 
+"Minds-Eye" all land on the same entry
+the id is what we want to encode in our binary pack
 
-we make the keys lowercase so that they are case insensitive
-we only need the value which is the index of the pokemon
-we keep the Vec<String> the original case
+ids used to be the line number of each entry, which meant inserting 
+anything but at the end of a file reshuffled every encoded team
+now they are written next to the entry and checked against 
+dex/ids.lock at build time
 
-further consideration can be done on nomenclature of Table
--> Array? Vec? idk
-cause HashMap and HashTable are the same thing, it could be confusing
+we normalise the keys so lookups ignore case and punctuation
+we keep the table values in their original case
 */
 
 #[derive(Debug, Default)]
 pub struct Dex {
+    pub generation: Generation,
     pub tables: Tables,
     pub maps: Maps,
+    // base stats, types and so on, see species.rs
+    pub species: HashMap<DexId, Species>,
+    // species -> moves it can learn in this generation
+    pub learnsets: HashMap<DexId, HashSet<DexId>>,
+    // type and category, see moves.rs
+    pub move_data: HashMap<DexId, MoveData>,
+    pub type_chart: TypeChart,
 }
 
 #[derive(Debug, Default)]
 pub struct Tables {
-    pub names:      Vec<String>,
-    pub items:      Vec<String>,
-    pub abilities:  Vec<String>,
-    pub moves:      Vec<String>,
-    pub natures:    Vec<String>,
-    pub teras:      Vec<String>,
+    pub names:      HashMap<DexId, String>,
+    pub items:      HashMap<DexId, String>,
+    pub abilities:  HashMap<DexId, String>,
+    pub moves:      HashMap<DexId, String>,
+    pub natures:    HashMap<DexId, String>,
+    pub teras:      HashMap<DexId, String>,
 }
 
 #[derive(Debug, Default)]
 pub struct Maps {
-    pub names:      HashMap<String, usize>,
-    pub items:      HashMap<String, usize>,
-    pub abilities:  HashMap<String, usize>,
-    pub moves:      HashMap<String, usize>,
-    pub natures:    HashMap<String, usize>,
-    pub teras:      HashMap<String, usize>,
+    pub names:      HashMap<String, DexId>,
+    pub items:      HashMap<String, DexId>,
+    pub abilities:  HashMap<String, DexId>,
+    pub moves:      HashMap<String, DexId>,
+    pub natures:    HashMap<String, DexId>,
+    pub teras:      HashMap<String, DexId>,
+}
+
+impl Tables {
+    pub fn get(&self, kind: EntryKind) -> &HashMap<DexId, String> {
+        match kind {
+            EntryKind::Species  => &self.names,
+            EntryKind::Item     => &self.items,
+            EntryKind::Ability  => &self.abilities,
+            EntryKind::Move     => &self.moves,
+            EntryKind::Nature   => &self.natures,
+            EntryKind::Tera     => &self.teras,
+        }
+    }
+}
+
+impl Maps {
+    pub fn get(&self, kind: EntryKind) -> &HashMap<String, DexId> {
+        match kind {
+            EntryKind::Species  => &self.names,
+            EntryKind::Item     => &self.items,
+            EntryKind::Ability  => &self.abilities,
+            EntryKind::Move     => &self.moves,
+            EntryKind::Nature   => &self.natures,
+            EntryKind::Tera     => &self.teras,
+        }
+    }
 }
 
 // the rest of the functions in this module feel self explanatory
 impl Dex {
-    pub fn build() -> Self {
-        let tables = parse_tables();
-        let maps = build_maps(&tables);
-        Dex {
+    // fails if two entries of the same kind share an id after toID,
+    // one of them could never be encoded
+    pub fn build(generation: Generation) -> Result<Self, DexError> {
+        let mut tables = parse_tables();
+        if let Some(files) = generation.files() {
+            restrict_tables(&mut tables, files);
+        }
+        let mut maps = build_maps(&tables)?;
+        // the alias files are written against the newest gen,
+        // older gens just skip aliases for things they don't have
+        let strict = generation.files().is_none();
+        add_aliases(&tables, &mut maps.names, EntryKind::Species, ALIAS_NAMES, strict)?;
+        add_aliases(&tables, &mut maps.items, EntryKind::Item, ALIAS_ITEMS, strict)?;
+        add_aliases(&tables, &mut maps.moves, EntryKind::Move, ALIAS_MOVES, strict)?;
+        let mut species = species::parse_species(SPECIES, &maps.abilities, strict)?;
+        species.retain(|id, _| tables.names.contains_key(id));
+        let mut learnsets = learnset::parse_learnsets(LEARNSETS, generation)?;
+        learnsets.retain(|species, _| tables.names.contains_key(species));
+        let mut move_data = moves::parse_moves(MOVE_DATA)?;
+        move_data.retain(|id, _| tables.moves.contains_key(id));
+        Ok(Dex {
+            generation,
             tables,
             maps,
-        }
+            species,
+            learnsets,
+            move_data,
+            type_chart: TypeChart::parse(TYPE_CHART)?,
+        })
+    }
+
+    // by name, None if the name isn't in the dex or we have no data for it
+    pub fn species_data(&self, name: &str) -> Option<&Species> {
+        let id = self.maps.names.get(&to_id(name))?;
+        self.species.get(id)
+    }
+
+    // how the dex spells a name, the parser lowercases them
+    // anything the dex doesn't know comes back as it was
+    pub fn canonical_name<'a>(&'a self, kind: EntryKind, name: &'a str) -> &'a str {
+        self.maps.get(kind)
+            .get(&to_id(name))
+            .and_then(|id| self.tables.get(kind).get(id))
+            .map_or(name, String::as_str)
+    }
+
+    pub fn species_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.canonical_name(EntryKind::Species, name)
+    }
+
+    // by name, None if the name isn't in the dex or we have no data for it
+    pub fn move_data(&self, name: &str) -> Option<&MoveData> {
+        let id = self.maps.moves.get(&to_id(name))?;
+        self.move_data.get(id)
+    }
+
+    // showdown's packed teams can give the ability as its slot on the
+    // species, "0", "1" or "H", this turns that into the ability's name
+    // anything else comes back as it was
+    pub fn resolve_ability<'a>(&'a self, species: &str, ability: &'a str) -> &'a str {
+        self.species_data(species)
+            .and_then(|data| data.ability_in_slot(ability))
+            .and_then(|id| self.tables.abilities.get(&id))
+            .map_or(ability, String::as_str)
+    }
+
+    // None when we have no learnset for the species, nothing to check then
+    // smeargle can sketch anything, so a species with sketch learns it all
+    pub fn can_learn(&self, species: DexId, move_id: DexId) -> Option<bool> {
+        let moves = self.learnsets.get(&species)?;
+        let sketch = self.maps.moves.get("sketch");
+        Some(moves.contains(&move_id) || sketch.is_some_and(|id| moves.contains(id)))
     }
 }
 
@@ -94,30 +342,229 @@ fn parse_tables() -> Tables {
     }
 }
 
-fn parse_table(file: &str) -> Vec<String> {
+// drop every id the generation didn't have
+fn restrict_tables(tables: &mut Tables, files: &GenFiles) {
+    let keep = |table: &mut HashMap<DexId, String>, file: &str| {
+        let ids = parse_table(file);
+        table.retain(|id, _| ids.contains_key(id));
+    };
+    keep(&mut tables.names, files.names);
+    keep(&mut tables.items, files.items);
+    keep(&mut tables.abilities, files.abilities);
+    keep(&mut tables.moves, files.moves);
+}
+
+// build.rs already rejected malformed lines, so we can skip them here
+// an empty name is the "nothing" entry, e.g. no item
+fn parse_table(file: &str) -> HashMap<DexId, String> {
     file
         .lines()
-        .map(String::from)
+        .filter_map(|line| line.split_once('\t'))
+        .filter_map(|(id, name)| Some((id.parse().ok()?, name.to_string())))
         .collect()
 }
 
-fn build_maps(tables: &Tables) -> Maps {
-    Maps {
-        names:      build_map(&tables.names),
-        items:      build_map(&tables.items),
-        abilities:  build_map(&tables.abilities),
-        moves:      build_map(&tables.moves), 
-        natures:    build_map(&tables.natures), 
-        teras:      build_map(&tables.teras), 
+fn build_maps(tables: &Tables) -> Result<Maps, DexError> {
+    Ok(Maps {
+        names:      build_map(tables, EntryKind::Species)?,
+        items:      build_map(tables, EntryKind::Item)?,
+        abilities:  build_map(tables, EntryKind::Ability)?,
+        moves:      build_map(tables, EntryKind::Move)?, 
+        natures:    build_map(tables, EntryKind::Nature)?, 
+        teras:      build_map(tables, EntryKind::Tera)?, 
+    })
+}
+
+// key on the toID so the input text is case and punctuation insensitive
+fn build_map(
+    tables: &Tables, 
+    kind: EntryKind
+) -> Result<HashMap<String, DexId>, DexError> {
+    let table = tables.get(kind);
+    let mut map = HashMap::with_capacity(table.len());
+    for (id, name) in table {
+        if let Some(other) = map.insert(to_id(name), *id) {
+            return Err(DexError::IdCollision {
+                kind,
+                first: table[&other].clone(),
+                second: name.clone(),
+            });
+        }
+    }
+    Ok(map)
+}
+
+// point each alias at the id of its canonical entry
+// an alias may not shadow a real entry or another alias
+// when strict is off, aliases for missing entries are skipped
+fn add_aliases(
+    tables: &Tables,
+    map: &mut HashMap<String, DexId>,
+    kind: EntryKind,
+    file: &str,
+    strict: bool
+) -> Result<(), DexError> {
+    for (alias, canonical) in file.lines().filter_map(|l| l.split_once('\t')) {
+        let Some(&id) = map.get(&to_id(canonical)) else {
+            if !strict {
+                continue;
+            }
+            return Err(DexError::UnknownAliasTarget {
+                kind,
+                alias: alias.to_string(),
+                target: canonical.to_string(),
+            });
+        };
+        if let Some(other) = map.insert(to_id(alias), id) {
+            return Err(DexError::IdCollision {
+                kind,
+                first: tables.get(kind)[&other].clone(),
+                second: alias.to_string(),
+            });
+        }
     }
+    Ok(())
 }
 
-// convert to lowercase to make the input text able to be case insensitive
-fn build_map(table: &Vec<String>) -> HashMap<String, usize> {
-    table
-        .iter()
-        .enumerate()
-        .map(|(i, t)| (t.to_lowercase(), i))
-        .collect::<HashMap<String, usize>>()
+// showdown's toID, lowercase and strip everything but [a-z0-9]
+// "Mind's Eye" -> "mindseye", "U-turn" -> "uturn"
+pub fn to_id(s: &str) -> String {
+    s
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Type;
+
+    // these ids are baked into every team packed so far
+    #[test]
+    fn test_ids_are_stable() {
+        let dex = Dex::build(Generation::Gen9).unwrap();
+        assert_eq!(dex.maps.names["bulbasaur"], 0);
+        assert_eq!(dex.maps.items["ejectpack"], 408);
+        assert_eq!(dex.maps.abilities["mindseye"], 304);
+        assert_eq!(dex.maps.moves["bloodmoon"], 902);
+        assert_eq!(dex.maps.natures["modest"], 14);
+        assert_eq!(dex.maps.teras["stellar"], 18);
+        assert_eq!(dex.tables.names[&1], "Ivysaur");
+        assert_eq!(dex.tables.items[&0], "");
+        assert_eq!(dex.maps.teras[""], 19);
+    }
+
+    #[test]
+    fn test_collisions_are_reported() {
+        let mut tables = parse_tables();
+        tables.moves.insert(5000, "uturn".into());
+        let err = build_maps(&tables).unwrap_err();
+        let DexError::IdCollision { kind, first, second } = err else {
+            panic!("expected a collision, got {:?}", err);
+        };
+        assert_eq!(kind, EntryKind::Move);
+        let mut names = [first, second];
+        names.sort();
+        assert_eq!(names, ["U-turn".to_string(), "uturn".to_string()]);
+    }
+
+    #[test]
+    fn test_aliases_resolve_to_canonical() {
+        let dex = Dex::build(Generation::Gen9).unwrap();
+        let id = dex.maps.names[&to_id("Necrozma-DM")];
+        assert_eq!(dex.tables.names[&id], "Necrozma-Dusk-Mane");
+        let id = dex.maps.moves[&to_id("Vice Grip")];
+        assert_eq!(dex.tables.moves[&id], "Vise Grip");
+        let id = dex.maps.items[&to_id("HDB")];
+        assert_eq!(dex.tables.items[&id], "Heavy-Duty Boots");
+    }
+
+    #[test]
+    fn test_bad_aliases_are_reported() {
+        let tables = parse_tables();
+        let mut maps = build_maps(&tables).unwrap();
+        let err = add_aliases(
+            &tables, &mut maps.moves, EntryKind::Move, "Spore\tSporeee", true
+        );
+        assert!(matches!(err, Err(DexError::UnknownAliasTarget { .. })));
+        let err = add_aliases(
+            &tables, &mut maps.moves, EntryKind::Move, "Spore\tProtect", true
+        );
+        assert!(matches!(err, Err(DexError::IdCollision { .. })));
+    }
+
+    #[test]
+    fn test_older_generations_are_subsets() {
+        let gen7 = Dex::build(Generation::Gen7).unwrap();
+        let gen8 = Dex::build(Generation::Gen8).unwrap();
+        let gen9 = Dex::build(Generation::Gen9).unwrap();
+        // same ids everywhere
+        assert_eq!(gen7.maps.names["pikachu"], gen9.maps.names["pikachu"]);
+        // z crystals and max moves
+        assert!(gen7.maps.items.contains_key("firiumz"));
+        assert!(!gen7.maps.moves.contains_key("maxairstream"));
+        assert!(gen8.maps.moves.contains_key("maxairstream"));
+        assert!(gen8.maps.moves.contains_key("gmaxwildfire"));
+        assert!(!gen8.maps.names.contains_key("gholdengo"));
+        assert!(!gen7.maps.names.contains_key("corviknight"));
+        assert!(!gen7.maps.names.contains_key("charizardgmax"));
+        // aliases for newer things are skipped, not errors
+        assert!(!gen7.maps.names.contains_key("urshifurapid"));
+        assert!(gen7.maps.items.contains_key("lefties"));
+    }
+
+    #[test]
+    fn test_generation_numbers() {
+        assert_eq!(Generation::try_from(8), Ok(Generation::Gen8));
+        assert_eq!(
+            Generation::try_from(3),
+            Err(DexError::UnsupportedGeneration { generation: 3 })
+        );
+        assert_eq!(Generation::default().to_string(), "Gen 9");
+        assert_eq!(Generation::from_format("gen7ou"), Some(Ok(Generation::Gen7)));
+        assert_eq!(
+            Generation::from_format("[Gen 8] VGC 2022"),
+            Some(Ok(Generation::Gen8))
+        );
+        assert!(matches!(Generation::from_format("gen4ou"), Some(Err(_))));
+        assert_eq!(Generation::from_format("ou"), None);
+    }
+
+    #[test]
+    fn test_resolve_ability() {
+        let mut dex = Dex::build(Generation::Gen9).unwrap();
+        let file = "932\t95/115/90/80/90/60\tFire/Dark\tBlaze//Intimidate\t830\t932";
+        dex.species = species::parse_species(file, &dex.maps.abilities, true).unwrap();
+
+        assert_eq!(dex.species_data("incineroar").unwrap().base_stats.spe, 60);
+        assert_eq!(dex.resolve_ability("Incineroar", "H"), "Intimidate");
+        assert_eq!(dex.resolve_ability("Incineroar", "0"), "Blaze");
+        // an empty slot, or a species we know nothing about, stays as it was
+        assert_eq!(dex.resolve_ability("Incineroar", "1"), "1");
+        assert_eq!(dex.resolve_ability("Pikachu", "H"), "H");
+        assert_eq!(dex.resolve_ability("Incineroar", "Blaze"), "Blaze");
+    }
+
+    #[test]
+    #[ignore = "needs dex/species.txt, run `npm run species` in dex_build"]
+    fn test_shipped_species() {
+        let dex = crate::get_dex();
+        let garchomp = dex.species_data("Garchomp").unwrap();
+        assert_eq!(garchomp.base_stats.to_array(), [108, 130, 95, 80, 85, 102]);
+        assert_eq!(garchomp.types.as_slice(), [Type::Dragon, Type::Ground]);
+        assert_eq!(dex.resolve_ability("Incineroar", "H"), "Intimidate");
+        assert_eq!(dex.resolve_ability("Incineroar", "0"), "Blaze");
+        let rotom = dex.maps.names["rotom"];
+        assert_eq!(dex.species_data("Rotom-Wash").unwrap().base_species, rotom);
+    }
+
+    #[test]
+    fn test_to_id() {
+        assert_eq!(to_id("Mind's Eye"), "mindseye");
+        assert_eq!(to_id("Ursaluna-Bloodmoon"), "ursalunabloodmoon");
+        assert_eq!(to_id("U-turn"), "uturn");
+        assert_eq!(to_id("Flabébé"), "flabb");
+    }
+}
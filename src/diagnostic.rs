@@ -0,0 +1,259 @@
+/*
+* diagnostic.rs
+*
+* where in the input a parse error happened, and printing it like a compiler
+* spans are always relative to the text the user gave us, not the block
+* or the trimmed line the parser was looking at
+*
+*  error: Level must be between 1 and 100, found '300'.
+*    --> Pokémon #2, line 14:8
+*     |
+*  14 | Level: 300
+*     |        ^^^
+*
+* warnings get the same treatment, a Diagnostic is either one flattened
+* into something the js side can show, see parser::check_pokepaste
+*/
+
+use serde::Serialize;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::error::{ParseError, ParseWarning};
+
+// block is the pokemon's place in the paste (from 0, like block_index)
+// line and column count from 1, column is in characters
+// start..end is the byte range in the original input
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct Span {
+    pub block:  usize,
+    pub line:   usize,
+    pub column: usize,
+    pub start:  usize,
+    pub end:    usize,
+}
+
+impl Span {
+    // start..end has to be inside input
+    pub fn new(input: &str, block: usize, start: usize, end: usize) -> Self {
+        let before = &input[..start];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        Span {
+            block,
+            line:   before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            start,
+            end,
+        }
+    }
+
+    // the first place part shows up in region, or all of region if it doesn't
+    // region is a slice of input that starts at byte offset
+    pub fn find(
+        input: &str,
+        block: usize,
+        offset: usize,
+        region: &str,
+        part: Option<&str>
+    ) -> Self {
+        let found = part
+            .filter(|part| !part.is_empty())
+            .and_then(|part| region.find(part).map(|i| (i, part.len())));
+        let (at, len) = found.unwrap_or((0, region.len()));
+        Span::new(input, block, offset + at, offset + at + len)
+    }
+}
+
+// errors and warnings both know where they are, or can find out
+pub(crate) trait Spanned: Sized {
+    fn span(&self) -> Option<Span>;
+    fn at(self, span: Span) -> Self;
+    // the bit of input it's about, None for the whole region
+    fn offending_text(&self) -> Option<&str>;
+}
+
+impl Spanned for ParseError {
+    fn span(&self) -> Option<Span> {
+        ParseError::span(self)
+    }
+
+    fn at(self, span: Span) -> Self {
+        ParseError::at(self, span)
+    }
+
+    fn offending_text(&self) -> Option<&str> {
+        ParseError::offending_text(self)
+    }
+}
+
+impl Spanned for ParseWarning {
+    fn span(&self) -> Option<Span> {
+        ParseWarning::span(self)
+    }
+
+    fn at(self, span: Span) -> Self {
+        ParseWarning::at(self, span)
+    }
+
+    fn offending_text(&self) -> Option<&str> {
+        ParseWarning::offending_text(self)
+    }
+}
+
+// gives an error the span of what it complains about inside region,
+// unless something closer to the problem already did
+pub(crate) fn locate<T: Spanned>(
+    err: T,
+    input: &str,
+    block: usize,
+    offset: usize,
+    region: &str
+) -> T {
+    if err.span().is_some() {
+        return err;
+    }
+    let span = Span::find(input, block, offset, region, err.offending_text());
+    err.at(span)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity:   Severity,
+    pub message:    String,
+    pub span:       Option<Span>,
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(err: &ParseError) -> Self {
+        Diagnostic {
+            severity:   Severity::Error,
+            message:    err.to_string().trim_end().to_string(),
+            span:       err.span(),
+        }
+    }
+}
+
+impl From<&ParseWarning> for Diagnostic {
+    fn from(warning: &ParseWarning) -> Self {
+        Diagnostic {
+            severity:   Severity::Warning,
+            message:    warning.to_string(),
+            span:       warning.span(),
+        }
+    }
+}
+
+// keep in sync with Span and Diagnostic
+// start and end are utf-8 byte offsets, line and column are what to show
+#[wasm_bindgen(typescript_custom_section)]
+const TYPESCRIPT: &str = r#"
+export interface Span {
+    block: number;
+    line: number;
+    column: number;
+    start: number;
+    end: number;
+}
+
+export interface Diagnostic {
+    severity: "error" | "warning";
+    message: string;
+    span: Span | null;
+}
+"#;
+
+pub fn render(input: &str, err: &ParseError) -> String {
+    render_diagnostic(input, &Diagnostic::from(err))
+}
+
+// the message, then the line it's on with the span underlined
+// ones without a span (json, empty input) are just the message
+pub fn render_diagnostic(input: &str, diagnostic: &Diagnostic) -> String {
+    let severity = match diagnostic.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    };
+    let message = format!("{}: {}", severity, diagnostic.message);
+    let Some(span) = diagnostic.span else {
+        return message;
+    };
+
+    let line_start = input[..span.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line_end = input[span.start..]
+        .find('\n')
+        .map(|i| span.start + i)
+        .unwrap_or(input.len());
+    let line = input[line_start..line_end].trim_end_matches('\r');
+    // spans don't cross lines, but cut them off at the end of this one if so
+    let underlined = &input[span.start..span.end.min(line_end)];
+
+    let number = span.line.to_string();
+    let gutter = " ".repeat(number.len());
+    format!(
+        "{}\n{}--> Pokémon #{}, line {}:{}\n{} |\n{} | {}\n{} | {}{}",
+        message,
+        gutter,
+        span.block + 1,
+        span.line,
+        span.column,
+        gutter,
+        number,
+        line,
+        gutter,
+        " ".repeat(span.column - 1),
+        "^".repeat(underlined.chars().count().max(1)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_span_positions() {
+        let input = "Pikachu\n\nÉevee\nLevel: 300";
+        let start = input.find("300").unwrap();
+        let span = Span::new(input, 1, start, start + 3);
+        assert_eq!(span.line, 4);
+        assert_eq!(span.column, 8);
+
+        let start = input.find("Éevee").unwrap();
+        let span = Span::find(input, 1, start, "Éevee", Some("vee"));
+        assert_eq!(span.column, 3);
+        assert_eq!(&input[span.start..span.end], "vee");
+        assert_eq!(Span::find(input, 1, start, "Éevee", None).end, start + 6);
+    }
+
+    #[test]
+    fn test_render() {
+        let input = "Pikachu\nLevel: 300\n";
+        let start = input.find("300").unwrap();
+        let err = ParseError::OutOfRange {
+            field: "Level",
+            value: "300".into(),
+            min: 1,
+            max: 100,
+            span: Some(Span::new(input, 0, start, start + 3)),
+        };
+        assert_eq!(
+            render(input, &err),
+            "error: Level must be between 1 and 100, found '300'.\n \
+             --> Pokémon #1, line 2:8\n  |\n2 | Level: 300\n  |        ^^^"
+        );
+        assert_eq!(render(input, &ParseError::EmptyInput), "error: Input Pokepaste was empty.");
+
+        let warning = ParseWarning::DefaultValue {
+            line: "Level: 300".into(),
+            span: Some(Span::new(input, 0, 8, 18)),
+        };
+        assert!(render_diagnostic(input, &(&warning).into()).starts_with(
+            "warning: 'Level: 300' is the default and does nothing.\n"
+        ));
+    }
+}
@@ -0,0 +1,414 @@
+/*
+* envelope.rs
+*
+* versioned wrapper around the packed records from binary.rs
+* a bare 21 byte record has no idea which dex it was built against,
+* so when the dex changes old blobs decode into the wrong pokemon
+*
+* layout:
+* [magic 3 bytes][format version u8][dex revision u16 BE][generation u8]
+* [count u8][format][team name][records...][nicknames...][happiness...]
+*
+* format and team name are one length byte followed by utf-8,
+* an empty one is just the 0 length byte
+*
+* the nickname section only exists if at least one pokemon has one,
+* otherwise the blob is just the header and the fixed 21 byte records
+* when it exists it holds one length prefixed utf-8 string per record,
+* a length of 0 meaning no nickname
+*
+* the happiness section only exists if at least one pokemon has some,
+* one byte per record, 255 meaning none (showdown's default anyway)
+* it always comes after a nickname section, an all empty one if no
+* pokemon has a nickname, so whatever is left after the nicknames
+* is happiness
+*
+* the generation picks which dex the records are decoded against,
+* it changes what the tera slot means (see codec.rs)
+*
+* the magic starts with 0xFF, a record can only start with 0xFF
+* if the species index is above 2039, so we can still tell apart
+* blobs from before the envelope existed (bare records)
+*/
+
+use crate::{
+    binary::{self, PokemonBin, TeamBin, RECORD_BYTES},
+    dex::{Generation, DEX_REVISION},
+    error::DecodeError,
+    model::MAX_HAPPINESS,
+};
+
+pub const MAGIC: [u8; 3] = [0xFF, b'P', b'K'];
+pub const FORMAT_VERSION: u8 = 1;
+// showdown refuses to battle with anything longer
+pub const MAX_NICKNAME_CHARS: usize = 18;
+
+const HEADER_BYTES: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub version:        u8,
+    pub dex_revision:   u16,
+    pub generation:     Generation,
+    pub count:          u8,
+}
+
+impl Header {
+    pub fn new(generation: Generation, count: u8) -> Self {
+        Header {
+            version:        FORMAT_VERSION,
+            dex_revision:   DEX_REVISION,
+            generation,
+            count,
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; HEADER_BYTES] {
+        let mut result = [0u8; HEADER_BYTES];
+        result[0..3].copy_from_slice(&MAGIC);
+        result[3] = self.version;
+        result[4..6].copy_from_slice(&self.dex_revision.to_be_bytes());
+        result[6] = self.generation.number();
+        result[7] = self.count;
+        result
+    }
+
+    // expects the magic to already have been checked
+    fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.len() < HEADER_BYTES {
+            return Err(DecodeError::TruncatedHeader);
+        }
+        let version = bytes[3];
+        if version != FORMAT_VERSION {
+            return Err(DecodeError::UnsupportedVersion { version });
+        }
+        let generation = Generation::from_number(bytes[6])
+            .ok_or(DecodeError::UnsupportedGeneration {
+                generation: bytes[6]
+            })?;
+        Ok(Header {
+            version,
+            dex_revision: u16::from_be_bytes([bytes[4], bytes[5]]),
+            generation,
+            count: bytes[7],
+        })
+    }
+}
+
+// the header and team metadata, then every record as its own chunk,
+// then the nickname and happiness sections if there are any
+// flatten for raw bytes, or print one chunk per line for base64/hex
+// codec::encode_team already checked the count and metadata lengths
+pub fn to_chunks(team: &TeamBin) -> Vec<Vec<u8>> {
+    let pokemon = &team.pokemon;
+    let mut chunks = Vec::with_capacity(pokemon.len() + 2);
+    let header = Header::new(team.generation, pokemon.len() as u8);
+    let mut head = header.to_bytes().to_vec();
+    write_prefixed(&mut head, &team.format);
+    write_prefixed(&mut head, &team.name);
+    chunks.push(head);
+    for p in pokemon {
+        chunks.push(p.pack_to_bytes().to_vec());
+    }
+
+    let happiness = pokemon.iter().any(|p| p.happiness.is_some());
+    if happiness || pokemon.iter().any(|p| !p.nickname.is_empty()) {
+        let mut section = Vec::new();
+        for p in pokemon {
+            // the parser already enforces the 18 char limit
+            // 18 chars of utf-8 is at most 72 bytes, fits a u8
+            write_prefixed(&mut section, &p.nickname);
+        }
+        chunks.push(section);
+    }
+    if happiness {
+        chunks.push(pokemon.iter().map(|p| p.happiness.unwrap_or(MAX_HAPPINESS)).collect());
+    }
+
+    chunks
+}
+
+pub fn to_bytes(team: &TeamBin) -> Vec<u8> {
+    to_chunks(team).concat()
+}
+
+// anything without our magic is treated as a legacy headerless blob
+pub fn from_bytes(bytes: &[u8]) -> Result<TeamBin, DecodeError> {
+    if !bytes.starts_with(&MAGIC) {
+        return Ok(TeamBin {
+            pokemon: split_records(bytes)?,
+            ..Default::default()
+        });
+    }
+
+    let header = Header::from_bytes(bytes)?;
+    check_dex_revision(header.dex_revision)?;
+    let mut body = &bytes[HEADER_BYTES..];
+    let format = read_prefixed(&mut body).ok_or(DecodeError::InvalidMetadata)?;
+    let name = read_prefixed(&mut body).ok_or(DecodeError::InvalidMetadata)?;
+
+    let records_len = header.count as usize * RECORD_BYTES;
+    if body.len() < records_len {
+        return Err(DecodeError::InvalidLength { len: bytes.len() });
+    }
+    let (records, nicknames) = body.split_at(records_len);
+    let mut pokemon = split_records(records)?;
+    if !nicknames.is_empty() {
+        let happiness = read_nicknames(nicknames, &mut pokemon)?;
+        if !happiness.is_empty() {
+            read_happiness(happiness, &mut pokemon)?;
+        }
+    }
+    Ok(TeamBin {
+        generation: header.generation,
+        format,
+        name,
+        pokemon,
+    })
+}
+
+// dex ids are append only, so anything packed against an older
+// revision still means the same thing, a newer one might not exist here
+fn check_dex_revision(found: u16) -> Result<(), DecodeError> {
+    if found > DEX_REVISION {
+        return Err(DecodeError::DexRevisionMismatch {
+            expected: DEX_REVISION,
+            found,
+        });
+    }
+    Ok(())
+}
+
+fn split_records(bytes: &[u8]) -> Result<Vec<PokemonBin>, DecodeError> {
+    // must be multiple of 21
+    if !bytes.len().is_multiple_of(RECORD_BYTES) {
+        return Err(DecodeError::InvalidLength { len: bytes.len() });
+    }
+
+    Ok(bytes
+        .chunks_exact(RECORD_BYTES)
+        .map(|chunk| {
+            let mut record = [0u8; RECORD_BYTES];
+            record.copy_from_slice(chunk);
+            binary::unpack_from_bytes(&record)
+        })
+        .collect())
+}
+
+// one length byte then the utf-8 bytes
+// callers make sure the string is at most 255 bytes
+fn write_prefixed(out: &mut Vec<u8>, s: &str) {
+    out.push(s.len() as u8);
+    out.extend_from_slice(s.as_bytes());
+}
+
+// the other way around for the metadata, moves bytes past what was read
+// None if it runs off the end or isn't utf-8
+fn read_prefixed(bytes: &mut &[u8]) -> Option<String> {
+    let (&len, rest) = bytes.split_first()?;
+    let len = len as usize;
+    if rest.len() < len {
+        return None;
+    }
+    let (s, rest) = rest.split_at(len);
+    *bytes = rest;
+    String::from_utf8(s.to_vec()).ok()
+}
+
+// gives back whatever comes after the section
+fn read_nicknames<'a>(
+    mut bytes: &'a [u8],
+    pokemon: &mut [PokemonBin]
+) -> Result<&'a [u8], DecodeError> {
+    for p in pokemon.iter_mut() {
+        let Some((&len, rest)) = bytes.split_first() else {
+            return Err(DecodeError::InvalidLength { len: bytes.len() });
+        };
+        let len = len as usize;
+        if rest.len() < len {
+            return Err(DecodeError::InvalidLength { len: rest.len() });
+        }
+        let (nickname, rest) = rest.split_at(len);
+        p.nickname = String::from_utf8(nickname.to_vec())
+            .map_err(|_| DecodeError::InvalidNickname)?;
+        if p.nickname.chars().count() > MAX_NICKNAME_CHARS {
+            return Err(DecodeError::InvalidNickname);
+        }
+        bytes = rest;
+    }
+    Ok(bytes)
+}
+
+// exactly one byte per record, anything else means the sections
+// didn't line up with the count
+fn read_happiness(
+    bytes: &[u8],
+    pokemon: &mut [PokemonBin]
+) -> Result<(), DecodeError> {
+    if bytes.len() != pokemon.len() {
+        return Err(DecodeError::InvalidLength { len: bytes.len() });
+    }
+    for (p, &happiness) in pokemon.iter_mut().zip(bytes) {
+        p.happiness = Some(happiness).filter(|&h| h != MAX_HAPPINESS);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECORD: [u8; RECORD_BYTES] = [
+        162, 113, 81, 35, 101, 8, 39, 224, 0, 0, 7,
+        229, 191, 255, 255, 255, 126, 153, 198, 16, 184
+    ];
+
+    fn sample() -> PokemonBin {
+        binary::unpack_from_bytes(&RECORD)
+    }
+
+    fn gen9(pokemon: Vec<PokemonBin>) -> TeamBin {
+        TeamBin { pokemon, ..Default::default() }
+    }
+
+    // no format or name, just their two length bytes
+    const EMPTY_METADATA_BYTES: usize = 2;
+
+    #[test]
+    fn test_header_roundtrip() {
+        let bytes = to_bytes(&gen9(vec![sample(), sample()]));
+        assert_eq!(
+            bytes.len(), 
+            HEADER_BYTES + EMPTY_METADATA_BYTES + 2 * RECORD_BYTES
+        );
+        assert!(bytes.starts_with(&MAGIC));
+        assert_eq!(from_bytes(&bytes).unwrap(), gen9(vec![sample(), sample()]));
+    }
+
+    #[test]
+    fn test_generation_roundtrip() {
+        let team = TeamBin {
+            generation: Generation::Gen7,
+            pokemon: vec![sample()],
+            ..Default::default()
+        };
+        let bytes = to_bytes(&team);
+        assert_eq!(bytes[6], 7);
+        assert_eq!(from_bytes(&bytes).unwrap(), team);
+    }
+
+    #[test]
+    fn test_metadata_roundtrip() {
+        let team = TeamBin {
+            generation: Generation::Gen9,
+            format: "gen9vgc2024regg".into(),
+            name: "Sun Room ☀".into(),
+            pokemon: vec![sample(), sample()],
+        };
+        let bytes = to_bytes(&team);
+        assert_eq!(bytes[7], 2);
+        assert_eq!(bytes[8] as usize, "gen9vgc2024regg".len());
+        assert_eq!(from_bytes(&bytes).unwrap(), team);
+    }
+
+    #[test]
+    fn test_rejects_truncated_metadata() {
+        let team = TeamBin { name: "Team".into(), ..Default::default() };
+        let mut bytes = to_bytes(&team);
+        bytes.pop();
+        assert_eq!(from_bytes(&bytes), Err(DecodeError::InvalidMetadata));
+    }
+
+    #[test]
+    fn test_rejects_unknown_generation() {
+        let mut bytes = to_bytes(&gen9(vec![sample()]));
+        bytes[6] = 4;
+        assert_eq!(
+            from_bytes(&bytes),
+            Err(DecodeError::UnsupportedGeneration { generation: 4 })
+        );
+    }
+
+    #[test]
+    fn test_nickname_roundtrip() {
+        let mut nicknamed = sample();
+        nicknamed.nickname = "Joe".into();
+        let team = vec![sample(), nicknamed];
+
+        let bytes = to_bytes(&gen9(team.clone()));
+        // one length byte per record plus "Joe"
+        assert_eq!(
+            bytes.len(), 
+            HEADER_BYTES + EMPTY_METADATA_BYTES + 2 * RECORD_BYTES + 2 + 3
+        );
+        assert_eq!(from_bytes(&bytes).unwrap(), gen9(team));
+    }
+
+    #[test]
+    fn test_happiness_roundtrip() {
+        let mut unhappy = sample();
+        unhappy.happiness = Some(0);
+        let team = vec![sample(), unhappy];
+
+        let bytes = to_bytes(&gen9(team.clone()));
+        // two empty nicknames, then one byte per record
+        assert_eq!(
+            bytes.len(),
+            HEADER_BYTES + EMPTY_METADATA_BYTES + 2 * RECORD_BYTES + 2 + 2
+        );
+        assert_eq!(bytes[bytes.len() - 2..], [MAX_HAPPINESS, 0]);
+        assert_eq!(from_bytes(&bytes).unwrap(), gen9(team.clone()));
+
+        // a happiness byte short, or one too many
+        let mut short = bytes.clone();
+        short.pop();
+        assert!(matches!(from_bytes(&short), Err(DecodeError::InvalidLength { .. })));
+        let mut long = bytes;
+        long.push(0);
+        assert!(matches!(from_bytes(&long), Err(DecodeError::InvalidLength { .. })));
+    }
+
+    #[test]
+    fn test_legacy_headerless_blob() {
+        let bytes = RECORD.to_vec();
+        assert_eq!(from_bytes(&bytes).unwrap(), gen9(vec![sample()]));
+    }
+
+    #[test]
+    fn test_rejects_unknown_version() {
+        let mut bytes = to_bytes(&gen9(vec![sample()]));
+        bytes[3] = 200;
+        assert_eq!(
+            from_bytes(&bytes),
+            Err(DecodeError::UnsupportedVersion { version: 200 })
+        );
+    }
+
+    #[test]
+    fn test_rejects_newer_dex_revision() {
+        let mut header = Header::new(Generation::Gen9, 1);
+        header.dex_revision = DEX_REVISION + 1;
+        let bytes = [header.to_bytes().as_slice(), &RECORD].concat();
+        assert!(matches!(
+            from_bytes(&bytes),
+            Err(DecodeError::DexRevisionMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rejects_truncated_header() {
+        assert_eq!(from_bytes(&MAGIC), Err(DecodeError::TruncatedHeader));
+    }
+
+    #[test]
+    fn test_rejects_short_nickname_section() {
+        let mut nicknamed = sample();
+        nicknamed.nickname = "Joe".into();
+        let mut bytes = to_bytes(&gen9(vec![nicknamed]));
+        bytes.pop();
+        assert!(matches!(
+            from_bytes(&bytes),
+            Err(DecodeError::InvalidLength { .. })
+        ));
+    }
+}
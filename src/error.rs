@@ -3,7 +3,15 @@ use std::{
     fmt,
 };
 use regex::Error as RegexError;
+use serde_json::Error as JsonError;
 
+use crate::{
+    diagnostic::Span,
+    dex::{EntryKind, Generation},
+};
+
+// span is where in the input it happened, see diagnostic.rs
+// it's None until the parser knows, and stays None for json
 #[derive(Debug)]
 pub enum ParseError {
     // input string is only whitespace
@@ -11,13 +19,29 @@ pub enum ParseError {
     // no information in a block
     EmptyBlock,
     // pokemon block is missing the name (required)
-    MissingName { block: String },
+    MissingName { block: String, span: Option<Span> },
     // EV or IV string is not in "VALUE STAT" format
-    MalformedTvString { line: String },
+    MalformedTvString { line: String, span: Option<Span> },
     // when a line is whack
-    MalformedLine { line: String },
+    MalformedLine { line: String, span: Option<Span> },
+    // showdown won't battle with nicknames over 18 characters
+    NicknameTooLong { nickname: String, span: Option<Span> },
+    // a number outside what the field allows, a 300 EV or level 0
+    OutOfRange {
+        field: &'static str,
+        value: String,
+        min: u8,
+        max: u8,
+        span: Option<Span>,
+    },
+    // not a number, nature, type or gender at all
+    InvalidValue { field: &'static str, value: String, span: Option<Span> },
+    // a set only has room for four moves
+    TooManyMoves { block: String, span: Option<Span> },
     // for regex creation
     Regex(RegexError),
+    // json that isn't a list of PokemonSet
+    Json(JsonError),
 }
 
 impl fmt::Display for ParseError {
@@ -29,22 +53,94 @@ impl fmt::Display for ParseError {
             ParseError::EmptyBlock => {
                 write!(f, "Found an empty Pokémon block.")
             },
-            ParseError::MissingName { block } => {
+            ParseError::MissingName { block, .. } => {
                 write!(
                     f, 
                     "Could not find a Pokemon name in this block: \n{}\n", 
                     block
                 )
             },
-            ParseError::MalformedTvString { line } => {
+            ParseError::MalformedTvString { line, .. } => {
                 write!(f, "Malofrmed EV/IV string {}", line)
             },
-            ParseError::MalformedLine { line } => {
+            ParseError::MalformedLine { line, .. } => {
                 write!(f, "Unrecognized or malformed line: '{}'", line)
-            }
+            },
+            ParseError::NicknameTooLong { nickname, .. } => {
+                write!(
+                    f, 
+                    "Nickname '{}' is longer than 18 characters.", 
+                    nickname
+                )
+            },
+            ParseError::OutOfRange { field, value, min, max, .. } => {
+                write!(
+                    f,
+                    "{} must be between {} and {}, found '{}'.",
+                    field,
+                    min,
+                    max,
+                    value
+                )
+            },
+            ParseError::InvalidValue { field, value, .. } => {
+                write!(f, "Invalid {}: '{}'", field, value)
+            },
+            ParseError::TooManyMoves { block, .. } => {
+                write!(
+                    f,
+                    "A Pokémon can only know 4 moves, this block has more: \n{}\n",
+                    block
+                )
+            },
             ParseError::Regex(err) => {
                 write!(f, "Regex compilation failed: {}", err)
             },
+            ParseError::Json(err) => {
+                write!(f, "Invalid team JSON: {}", err)
+            },
+        }
+    }
+}
+
+impl ParseError {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ParseError::MissingName { span, .. }
+            | ParseError::MalformedTvString { span, .. }
+            | ParseError::MalformedLine { span, .. }
+            | ParseError::NicknameTooLong { span, .. }
+            | ParseError::OutOfRange { span, .. }
+            | ParseError::InvalidValue { span, .. }
+            | ParseError::TooManyMoves { span, .. } => *span,
+            _ => None,
+        }
+    }
+
+    // errors that aren't about a place in the input ignore this
+    pub fn at(mut self, at: Span) -> Self {
+        match &mut self {
+            ParseError::MissingName { span, .. }
+            | ParseError::MalformedTvString { span, .. }
+            | ParseError::MalformedLine { span, .. }
+            | ParseError::NicknameTooLong { span, .. }
+            | ParseError::OutOfRange { span, .. }
+            | ParseError::InvalidValue { span, .. }
+            | ParseError::TooManyMoves { span, .. } => *span = Some(at),
+            _ => {},
+        }
+        self
+    }
+
+    // the bit of input the error is about, to find it again for the span
+    pub fn offending_text(&self) -> Option<&str> {
+        match self {
+            ParseError::MalformedTvString { line, .. }
+            | ParseError::MalformedLine { line, .. } => Some(line),
+            ParseError::NicknameTooLong { nickname, .. } => Some(nickname),
+            ParseError::OutOfRange { value, .. }
+            | ParseError::InvalidValue { value, .. } => Some(value),
+            _ => None,
         }
     }
 }
@@ -55,4 +151,367 @@ impl From<RegexError> for ParseError {
     }
 }
 
+impl From<JsonError> for ParseError {
+    fn from(err: JsonError) -> Self {
+        ParseError::Json(err)
+    }
+}
+
 //impl Error for ParseError {}
+
+// things the parser skipped or that look like a mistake,
+// they never change what gets parsed, see parser::check_pokepaste
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseWarning {
+    // not a line showdown writes, e.g. "Line that does nothing"
+    UnknownLine { line: String, span: Option<Span> },
+    // a showdown field with nowhere to go in the pack, e.g. Pokeball
+    DroppedField { field: String, span: Option<Span> },
+    // EV or IV entry whose stat isn't one of the six
+    UnknownStat { stat: String, span: Option<Span> },
+    // the same move twice in one set
+    DuplicateMove { name: String, span: Option<Span> },
+    // "Shiny: No", writing the default out does nothing
+    DefaultValue { line: String, span: Option<Span> },
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseWarning::UnknownLine { line, .. } => {
+                write!(f, "Unrecognized line '{}' was ignored.", line)
+            },
+            ParseWarning::DroppedField { field, .. } => {
+                write!(f, "{} can't be packed and was dropped.", field)
+            },
+            ParseWarning::UnknownStat { stat, .. } => {
+                write!(f, "Unknown stat '{}' was ignored.", stat)
+            },
+            ParseWarning::DuplicateMove { name, .. } => {
+                write!(f, "{} is listed more than once.", name)
+            },
+            ParseWarning::DefaultValue { line, .. } => {
+                write!(f, "'{}' is the default and does nothing.", line)
+            },
+        }
+    }
+}
+
+impl ParseWarning {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ParseWarning::UnknownLine { span, .. }
+            | ParseWarning::DroppedField { span, .. }
+            | ParseWarning::UnknownStat { span, .. }
+            | ParseWarning::DuplicateMove { span, .. }
+            | ParseWarning::DefaultValue { span, .. } => *span,
+        }
+    }
+
+    pub fn at(mut self, at: Span) -> Self {
+        match &mut self {
+            ParseWarning::UnknownLine { span, .. }
+            | ParseWarning::DroppedField { span, .. }
+            | ParseWarning::UnknownStat { span, .. }
+            | ParseWarning::DuplicateMove { span, .. }
+            | ParseWarning::DefaultValue { span, .. } => *span = Some(at),
+        }
+        self
+    }
+
+    pub fn offending_text(&self) -> Option<&str> {
+        match self {
+            ParseWarning::UnknownStat { stat, .. } => Some(stat),
+            ParseWarning::DuplicateMove { name, .. } => Some(name),
+            // the whole line
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    // blob is too short to hold the envelope header
+    TruncatedHeader,
+    // format version we do not know how to read
+    UnsupportedVersion { version: u8 },
+    // blob was packed against a newer dex than this build knows about
+    DexRevisionMismatch { expected: u16, found: u16 },
+    // records do not line up on 21 byte boundaries
+    InvalidLength { len: usize },
+    // nickname section is not valid utf-8 or is too long
+    InvalidNickname,
+    // header names a generation we have no dex for
+    UnsupportedGeneration { generation: u8 },
+    // team name or format is not valid utf-8
+    InvalidMetadata,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::TruncatedHeader => {
+                write!(f, "Packed data is too short to contain a header.")
+            },
+            DecodeError::UnsupportedVersion { version } => {
+                write!(f, "Unsupported pokepack format version: {}", version)
+            },
+            DecodeError::DexRevisionMismatch { expected, found } => {
+                write!(
+                    f,
+                    "Packed with dex revision {}, this build uses revision {}.",
+                    found,
+                    expected
+                )
+            },
+            DecodeError::InvalidLength { len } => {
+                write!(f, "Invalid input length: {} bytes.", len)
+            },
+            DecodeError::InvalidNickname => {
+                write!(f, "Packed data contains an invalid nickname.")
+            },
+            DecodeError::UnsupportedGeneration { generation } => {
+                write!(f, "Packed data uses unsupported generation {}.", generation)
+            },
+            DecodeError::InvalidMetadata => {
+                write!(f, "Packed data contains an invalid team name or format.")
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodeError {
+    // name isn't in the dex, block_index is the pokemon's place in the paste
+    // suggestions are the closest dex names, best first
+    UnknownEntry {
+        kind: EntryKind,
+        value: String,
+        block_index: usize,
+        suggestions: Vec<String>,
+    },
+    // tera type outside gen 9, dynamax outside gen 8, and so on
+    NotInGeneration {
+        field: &'static str,
+        generation: Generation,
+        block_index: usize,
+    },
+    // the container counts members in a single byte
+    TooManyPokemon { count: usize },
+    // team name or format doesn't fit its length byte
+    MetadataTooLong { field: &'static str, len: usize },
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::UnknownEntry { 
+                kind, 
+                value, 
+                block_index, 
+                suggestions 
+            } => {
+                write!(
+                    f,
+                    "Unknown {} '{}' in Pokémon #{}.",
+                    kind,
+                    value,
+                    block_index + 1
+                )?;
+                if !suggestions.is_empty() {
+                    write!(f, " Did you mean: {}?", suggestions.join(", "))?;
+                }
+                Ok(())
+            },
+            EncodeError::NotInGeneration { field, generation, block_index } => {
+                write!(
+                    f,
+                    "{} does not exist in {}, found in Pokémon #{}.",
+                    field,
+                    generation,
+                    block_index + 1
+                )
+            },
+            EncodeError::TooManyPokemon { count } => {
+                write!(
+                    f, 
+                    "A team can hold at most 255 Pokémon, found {}.", 
+                    count
+                )
+            },
+            EncodeError::MetadataTooLong { field, len } => {
+                write!(
+                    f, 
+                    "The {} is {} bytes long, the limit is 255.", 
+                    field, 
+                    len
+                )
+            },
+        }
+    }
+}
+
+// everything that can go wrong between a paste and packed bytes
+#[derive(Debug)]
+pub enum PokepackError {
+    Parse(ParseError),
+    Encode(EncodeError),
+    Decode(DecodeError),
+    Dex(DexError),
+}
+
+impl fmt::Display for PokepackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PokepackError::Parse(err) => write!(f, "{}", err),
+            PokepackError::Encode(err) => write!(f, "{}", err),
+            PokepackError::Decode(err) => write!(f, "{}", err),
+            PokepackError::Dex(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<ParseError> for PokepackError {
+    fn from(err: ParseError) -> Self {
+        PokepackError::Parse(err)
+    }
+}
+
+impl From<EncodeError> for PokepackError {
+    fn from(err: EncodeError) -> Self {
+        PokepackError::Encode(err)
+    }
+}
+
+impl From<DecodeError> for PokepackError {
+    fn from(err: DecodeError) -> Self {
+        PokepackError::Decode(err)
+    }
+}
+
+impl From<DexError> for PokepackError {
+    fn from(err: DexError) -> Self {
+        PokepackError::Dex(err)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DexError {
+    // two entries (or aliases) normalise to the same showdown id
+    IdCollision { kind: EntryKind, first: String, second: String },
+    // alias points at something that isn't in the dex
+    UnknownAliasTarget { kind: EntryKind, alias: String, target: String },
+    // asked for a generation we have no dex for
+    UnsupportedGeneration { generation: u8 },
+    // a format we have no ruleset for, see validate.rs
+    UnsupportedFormat { format: String },
+    // dex/learnsets.bin ends in the middle of an entry
+    MalformedLearnsets,
+    // a dex/species.txt line we can't read, see species.rs
+    MalformedSpecies { line: String },
+    // a dex/typechart.txt line we can't read, or a type with no line
+    MalformedTypeChart { line: String },
+    // a dex/movedata.txt line we can't read, see moves.rs
+    MalformedMove { line: String },
+}
+
+impl fmt::Display for DexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DexError::IdCollision { kind, first, second } => {
+                write!(
+                    f,
+                    "Dex {} entries '{}' and '{}' have the same ID.",
+                    kind,
+                    first,
+                    second
+                )
+            },
+            DexError::UnknownAliasTarget { kind, alias, target } => {
+                write!(
+                    f,
+                    "Dex {} alias '{}' points at unknown entry '{}'.",
+                    kind,
+                    alias,
+                    target
+                )
+            },
+            DexError::UnsupportedGeneration { generation } => {
+                write!(f, "Unsupported generation: {}", generation)
+            },
+            DexError::UnsupportedFormat { format } => {
+                write!(f, "No ruleset for format: {}", format)
+            },
+            DexError::MalformedLearnsets => {
+                write!(f, "Learnset data is malformed.")
+            },
+            DexError::MalformedSpecies { line } => {
+                write!(f, "Malformed species entry: {}", line)
+            },
+            DexError::MalformedTypeChart { line } => {
+                write!(f, "Malformed type chart entry: {}", line)
+            },
+            DexError::MalformedMove { line } => {
+                write!(f, "Malformed move entry: {}", line)
+            },
+        }
+    }
+}
+
+// why a damage calc (damage.rs) couldn't be done
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CalcError {
+    // not in the dex, or the dex has no base stats or types for it
+    UnknownSpecies { name: String },
+    // not in the dex, or no type, category or power for it
+    UnknownMove { name: String },
+    // status moves don't do damage
+    StatusMove { name: String },
+    // low kick, seismic toss and friends, their power depends on the target
+    VariablePower { name: String },
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalcError::UnknownSpecies { name } => {
+                write!(f, "No species data for '{}'.", name)
+            },
+            CalcError::UnknownMove { name } => {
+                write!(f, "No move data for '{}'.", name)
+            },
+            CalcError::StatusMove { name } => {
+                write!(f, "'{}' is a status move.", name)
+            },
+            CalcError::VariablePower { name } => {
+                write!(f, "'{}' has no fixed base power.", name)
+            },
+        }
+    }
+}
+
+// why the spread optimiser (optimise.rs) came up empty
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpreadError {
+    // a benchmark couldn't be calculated at all
+    Calc(CalcError),
+    // no spread within the EV limits meets every benchmark
+    Unreachable,
+}
+
+impl fmt::Display for SpreadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpreadError::Calc(err) => write!(f, "{}", err),
+            SpreadError::Unreachable => {
+                write!(f, "No EV spread meets every benchmark.")
+            },
+        }
+    }
+}
+
+impl From<CalcError> for SpreadError {
+    fn from(err: CalcError) -> Self {
+        SpreadError::Calc(err)
+    }
+}
@@ -0,0 +1,193 @@
+/*
+* export.rs
+*
+* writes a team exactly the way showdown's exporter (Teams.exportSet) does,
+* byte for byte, so diffs between two exports only show real changes
+* impl Display for Pokemon is our own looser format, this one is fixed:
+*
+*   Nickname (Species) (M) @ Item
+*   Ability: ...
+*   Level: ...                  not for level 100
+*   Shiny: Yes
+*   Happiness: ...              not for 255
+*   Pokeball                    never, we don't keep it
+*   Hidden Power: ...
+*   Dynamax Level: ...          not for 10
+*   Gigantamax: Yes
+*   Tera Type: ...
+*   EVs: ...                    HP Atk Def SpA SpD Spe, no 0s
+*   ... Nature
+*   IVs: ...                    same order, no 31s
+*   - Move                      hidden power moves as "Hidden Power [Fire]"
+*
+* every line ends in two spaces, every set is followed by a blank line
+* names are spelled the way the dex spells them
+* the expected exports in tests/golden come from Teams.exportSet itself,
+* `npm run golden` in dex_build (golden.js) rewrites them from @pkmn/sim
+* the ones in the repo were still written by hand, run it before
+* trusting them
+*/
+
+use std::fmt::Write;
+
+use crate::{
+    dex::{Dex, EntryKind},
+    model::{Nature, Stats, Type, MAX_HAPPINESS, MAX_IV, MAX_LEVEL, STAT_LABELS},
+    parser::Pokemon,
+};
+
+// showdown's default when no dynamax level is given
+const DEFAULT_DYNAMAX_LEVEL: u8 = 10;
+
+pub fn export_set(dex: &Dex, pokemon: &Pokemon) -> String {
+    let mut out = String::new();
+    let species = dex.canonical_name(EntryKind::Species, &pokemon.name);
+    // writing to a String can't fail
+    let mut line = |text: String| {
+        let _ = writeln!(out, "{}  ", text);
+    };
+
+    let mut first = if pokemon.nickname.is_empty() || pokemon.nickname == species {
+        species.to_string()
+    } else {
+        format!("{} ({})", pokemon.nickname, species)
+    };
+    if let Some(gender) = pokemon.gender {
+        first += &format!(" ({})", gender.letter());
+    }
+    if !pokemon.item.is_empty() {
+        first += &format!(" @ {}", dex.canonical_name(EntryKind::Item, &pokemon.item));
+    }
+    line(first);
+
+    if !pokemon.ability.is_empty() {
+        let ability = dex.resolve_ability(&pokemon.name, &pokemon.ability);
+        line(format!("Ability: {}", dex.canonical_name(EntryKind::Ability, ability)));
+    }
+    if let Some(level) = pokemon.level.filter(|&level| level != MAX_LEVEL) {
+        line(format!("Level: {}", level));
+    }
+    if pokemon.shiny {
+        line("Shiny: Yes".to_string());
+    }
+    if let Some(happiness) = pokemon.happiness.filter(|&h| h != MAX_HAPPINESS) {
+        line(format!("Happiness: {}", happiness));
+    }
+    if let Some(hidden_power) = pokemon.hidden_power {
+        line(format!("Hidden Power: {}", hidden_power));
+    }
+    let dynamax_level = pokemon.dynamax_level.filter(|&level| level != DEFAULT_DYNAMAX_LEVEL);
+    if let Some(level) = dynamax_level {
+        line(format!("Dynamax Level: {}", level));
+    }
+    if pokemon.gigantamax {
+        line("Gigantamax: Yes".to_string());
+    }
+    if let Some(tera) = pokemon.tera {
+        line(format!("Tera Type: {}", tera));
+    }
+    if let Some(evs) = spread(&pokemon.evs, 0) {
+        line(format!("EVs: {}", evs));
+    }
+    if let Some(nature) = pokemon.nature {
+        line(format!("{} Nature", Nature::name(nature)));
+    }
+    if let Some(ivs) = spread(&pokemon.ivs, MAX_IV) {
+        line(format!("IVs: {}", ivs));
+    }
+    for m in &pokemon.moves {
+        line(format!("- {}", move_name(dex, m.name())));
+    }
+    out
+}
+
+pub fn export_team(dex: &Dex, team: &[Pokemon]) -> String {
+    team.iter()
+        .map(|pokemon| export_set(dex, pokemon) + "\n")
+        .collect()
+}
+
+// "252 Atk / 4 SpD / 252 Spe", None when every stat is the default
+fn spread(stats: &Stats<u8>, default: u8) -> Option<String> {
+    let parts: Vec<String> = stats
+        .to_array()
+        .iter()
+        .zip(STAT_LABELS)
+        .filter(|(value, _)| **value != default)
+        .map(|(value, label)| format!("{} {}", value, label))
+        .collect();
+    (!parts.is_empty()).then(|| parts.join(" / "))
+}
+
+// the dex has "Hidden Power Fire", showdown exports "Hidden Power [Fire]"
+fn move_name(dex: &Dex, name: &str) -> String {
+    let name = dex.canonical_name(EntryKind::Move, name);
+    match name.strip_prefix("Hidden Power ") {
+        Some(t) if t.parse::<Type>().is_ok() => format!("Hidden Power [{}]", t),
+        _ => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{codec, dex::Generation, get_dex_for, parser};
+    use std::{fs, path::Path};
+
+    // what a team looks like after being packed and unpacked
+    fn decoded(paste: &str, generation: Generation) -> Vec<Pokemon> {
+        let dex = get_dex_for(generation);
+        let team = parser::parse_pokepaste(paste.to_string()).unwrap();
+        codec::encode_all_pokemon(dex, team)
+            .unwrap()
+            .iter()
+            .map(|pbin| codec::pokebin_to_string(dex, pbin))
+            .collect()
+    }
+
+    // tests/golden/<name>.in.txt is packed with the gen in its name
+    // (gen7-..., gen8-..., anything else is gen 9), decoded, exported and
+    // compared against <name>.out.txt, see the top of the file
+    #[test]
+    fn test_golden_exports() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+        let mut checked = 0;
+        for entry in fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            let file = path.file_name().unwrap().to_string_lossy().to_string();
+            let Some(name) = file.strip_suffix(".in.txt") else {
+                continue;
+            };
+            let generation = match name.split('-').next() {
+                Some("gen7") => Generation::Gen7,
+                Some("gen8") => Generation::Gen8,
+                _ => Generation::Gen9,
+            };
+            let input = fs::read_to_string(&path).unwrap();
+            let expected = fs::read_to_string(dir.join(format!("{}.out.txt", name))).unwrap();
+            let team = decoded(&input, generation);
+            let output = export_team(get_dex_for(generation), &team);
+            assert_eq!(output, expected, "{}", name);
+
+            // exporting is stable, an export reads back into the same export
+            let again = decoded(&output, generation);
+            assert_eq!(export_team(get_dex_for(generation), &again), expected, "{}", name);
+            checked += 1;
+        }
+        assert!(checked >= 5, "only {} golden files", checked);
+    }
+
+    #[test]
+    fn test_export_set() {
+        let dex = crate::get_dex();
+        let paste = "garchomp (garchomp) @ choice scarf\nLevel: 100\n- dragon claw\n- earthquake";
+        let team = parser::parse_pokepaste(paste.to_string()).unwrap();
+        // showdown compares the nickname to the species exactly
+        assert_eq!(
+            export_set(dex, &team[0]),
+            "garchomp (Garchomp) @ Choice Scarf  \n- Dragon Claw  \n- Earthquake  \n"
+        );
+        assert_eq!(move_name(dex, "hiddenpowerice"), "Hidden Power [Ice]");
+        assert_eq!(move_name(dex, "Not A Move"), "Not A Move");
+    }
+}
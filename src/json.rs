@@ -0,0 +1,293 @@
+/*
+* json.rs
+*
+* showdown's PokemonSet as json, what most tools pass sets around as
+* https://github.com/smogon/pokemon-showdown/blob/master/sim/teams.ts
+*
+* Pokemon serialises through PokemonSet, so serde_json works on it directly
+* values are checked the same way the paste parser checks them, a 300 EV
+* is an error here too
+* pokeball is accepted but dropped, Pokemon has no room for it
+*/
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    envelope::MAX_NICKNAME_CHARS,
+    error::ParseError,
+    model::{
+        self, Gender, Nature, Stats, Type,
+        MAX_DYNAMAX_LEVEL, MAX_EV, MAX_IV, MAX_LEVEL, MAX_MOVES, MIN_LEVEL,
+    },
+    parser::Pokemon,
+};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PokemonSet {
+    // the nickname, or the species again if there isn't one
+    pub name:           String,
+    pub species:        String,
+    pub item:           String,
+    pub ability:        String,
+    pub moves:          Vec<String>,
+    pub nature:         String,
+    pub gender:         String,
+    pub evs:            StatsTable,
+    pub ivs:            StatsTable,
+    // left out when the set doesn't give one, showdown reads that as 100
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level:          Option<u8>,
+    #[serde(skip_serializing_if = "is_false")]
+    pub shiny:          bool,
+    // left out when not given, showdown reads that as 255
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub happiness:      Option<u8>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub hp_type:        String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dynamax_level:  Option<u8>,
+    #[serde(skip_serializing_if = "is_false")]
+    pub gigantamax:     bool,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub tera_type:      String,
+}
+
+// showdown always writes all six, but hand written json often doesn't
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatsTable {
+    pub hp:     Option<u8>,
+    pub atk:    Option<u8>,
+    pub def:    Option<u8>,
+    pub spa:    Option<u8>,
+    pub spd:    Option<u8>,
+    pub spe:    Option<u8>,
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+impl StatsTable {
+    fn from_stats(stats: &Stats<u8>) -> Self {
+        let [hp, atk, def, spa, spd, spe] = stats.to_array().map(Some);
+        StatsTable { hp, atk, def, spa, spd, spe }
+    }
+
+    // missing stats are the default, same as a paste that leaves them out
+    fn to_stats(
+        &self,
+        field: &'static str,
+        default: u8,
+        max: u8
+    ) -> Result<Stats<u8>, ParseError> {
+        let values = [self.hp, self.atk, self.def, self.spa, self.spd, self.spe];
+        let mut stats = [default; 6];
+        for (stat, value) in stats.iter_mut().zip(values) {
+            if let Some(value) = value {
+                *stat = model::check_range(field, value, 0, max)?;
+            }
+        }
+        Ok(Stats::from_array(stats))
+    }
+}
+
+impl From<Pokemon> for PokemonSet {
+    fn from(pokemon: Pokemon) -> Self {
+        let name = if pokemon.nickname.is_empty() {
+            pokemon.name.clone()
+        } else {
+            pokemon.nickname.clone()
+        };
+        let type_name = |t: Option<Type>| t.map(Type::name).unwrap_or_default();
+        PokemonSet {
+            name,
+            species:        pokemon.name,
+            item:           pokemon.item,
+            ability:        pokemon.ability,
+            moves:          pokemon.moves.iter().map(|m| m.to_string()).collect(),
+            nature:         pokemon.nature.map(Nature::name).unwrap_or_default().into(),
+            gender:         pokemon.gender.map(Gender::letter).unwrap_or_default().into(),
+            evs:            StatsTable::from_stats(&pokemon.evs),
+            ivs:            StatsTable::from_stats(&pokemon.ivs),
+            level:          pokemon.level,
+            shiny:          pokemon.shiny,
+            happiness:      pokemon.happiness,
+            hp_type:        type_name(pokemon.hidden_power).into(),
+            dynamax_level:  pokemon.dynamax_level,
+            gigantamax:     pokemon.gigantamax,
+            tera_type:      type_name(pokemon.tera).into(),
+        }
+    }
+}
+
+// same checks as the paste parser, a level of 0 is one that wasn't given,
+// the same as showdown's `set.level || 100`
+impl TryFrom<PokemonSet> for Pokemon {
+    type Error = ParseError;
+
+    fn try_from(set: PokemonSet) -> Result<Self, Self::Error> {
+        // a name that is just the species isn't a nickname
+        let nickname = if set.name == set.species { "" } else { &set.name };
+        // old tools only fill in name
+        let species = if set.species.is_empty() { &set.name } else { &set.species };
+        let level = match set.level {
+            None | Some(0) => None,
+            Some(level) => Some(model::check_range("Level", level, MIN_LEVEL, MAX_LEVEL)?),
+        };
+        let dynamax_level = match set.dynamax_level {
+            Some(level) => {
+                Some(model::check_range("Dynamax Level", level, 0, MAX_DYNAMAX_LEVEL)?)
+            },
+            None => None,
+        };
+        if set.moves.len() > MAX_MOVES {
+            return Err(ParseError::TooManyMoves {
+                block: set.moves.join(", "),
+                span: None,
+            });
+        }
+        // showdown leaves empty strings in for unset slots
+        let moves = set
+            .moves
+            .iter()
+            .filter(|m| !m.trim().is_empty())
+            .map(|m| m.parse())
+            .collect::<Result<_, _>>()?;
+        Ok(Pokemon {
+            nickname:       nickname.to_string(),
+            name:           species.to_string(),
+            gender:         Gender::parse(&set.gender)?,
+            item:           set.item,
+            ability:        set.ability,
+            level,
+            shiny:          set.shiny,
+            happiness:      set.happiness,
+            hidden_power:   model::parse_optional(&set.hp_type)?,
+            dynamax_level,
+            gigantamax:     set.gigantamax,
+            tera:           model::parse_optional(&set.tera_type)?,
+            evs:            set.evs.to_stats("EVs", 0, MAX_EV)?,
+            nature:         model::parse_optional(&set.nature)?,
+            ivs:            set.ivs.to_stats("IVs", MAX_IV, MAX_IV)?,
+            moves,
+        })
+    }
+}
+
+// a team is a json array of sets
+// read as sets first so a bad value keeps its own error, not a serde one
+pub fn parse_json_team(json: &str) -> Result<Vec<Pokemon>, ParseError> {
+    if json.trim().is_empty() {
+        return Err(ParseError::EmptyInput);
+    }
+    let sets: Vec<PokemonSet> = serde_json::from_str(json)?;
+    let team = sets
+        .into_iter()
+        .map(Pokemon::try_from)
+        .collect::<Result<Vec<Pokemon>, ParseError>>()?;
+    if let Some(pokemon) = team.iter().find(|p| p.name.is_empty()) {
+        return Err(ParseError::MissingName {
+            block: pokemon.to_string(),
+            span: None,
+        });
+    }
+    let too_long = |p: &&Pokemon| p.nickname.chars().count() > MAX_NICKNAME_CHARS;
+    if let Some(pokemon) = team.iter().find(too_long) {
+        return Err(ParseError::NicknameTooLong {
+            nickname: pokemon.nickname.clone(),
+            span: None,
+        });
+    }
+    Ok(team)
+}
+
+pub fn write_json_team(team: &[Pokemon]) -> String {
+    // only fails on maps with non string keys, we have none
+    serde_json::to_string(team).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SET: &str = r#"[{
+        "name": "Joe",
+        "species": "Glimmora",
+        "item": "Focus Sash",
+        "ability": "Toxic Debris",
+        "moves": ["Mortal Spin", "Power Gem"],
+        "nature": "Timid",
+        "gender": "M",
+        "evs": {"hp": 0, "atk": 0, "def": 4, "spa": 252, "spd": 0, "spe": 252},
+        "ivs": {"hp": 31, "atk": 0, "def": 31, "spa": 31, "spd": 31, "spe": 31},
+        "level": 50,
+        "shiny": true,
+        "happiness": 255,
+        "teraType": "Grass"
+    }]"#;
+
+    #[test]
+    fn test_parse_json_team() {
+        let team = parse_json_team(SET).unwrap();
+        let joe = &team[0];
+        assert_eq!(joe.nickname, "Joe");
+        assert_eq!(joe.name, "Glimmora");
+        assert_eq!(joe.gender, Some(Gender::Male));
+        assert_eq!(joe.level, Some(50));
+        assert!(joe.shiny);
+        assert_eq!(joe.happiness, Some(255));
+        assert_eq!(joe.tera, Some(Type::Grass));
+        assert_eq!(joe.nature, Some(Nature::Timid));
+        assert_eq!(joe.evs.spa, 252);
+        assert_eq!(joe.ivs.atk, 0);
+        assert_eq!(joe.moves.as_slice(), ["Mortal Spin", "Power Gem"]);
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let team = parse_json_team(SET).unwrap();
+        let json = write_json_team(&team);
+        assert!(json.contains(r#""teraType":"Grass""#));
+        assert!(!json.contains("gigantamax"));
+        assert_eq!(parse_json_team(&json).unwrap(), team);
+    }
+
+    #[test]
+    fn test_partial_sets() {
+        let team = parse_json_team(r#"[{"species": "Pikachu", "evs": {"spe": 252}}]"#)
+            .unwrap();
+        assert_eq!(team[0].name, "Pikachu");
+        assert!(team[0].nickname.is_empty());
+        assert_eq!(team[0].evs.spe, 252);
+        assert_eq!(team[0].evs.hp, 0);
+        assert_eq!(team[0].ivs.hp, 31);
+        assert_eq!(team[0].level, None);
+    }
+
+    #[test]
+    fn test_no_level_roundtrip() {
+        let team = parse_json_team(r#"[{"species": "Pikachu"}]"#).unwrap();
+        let set = PokemonSet::from(team[0].clone());
+        assert_eq!(set.level, None);
+        assert!(serde_json::to_value(&set).unwrap().get("level").is_none());
+        assert_eq!(PokemonSet::from(Pokemon::try_from(set.clone()).unwrap()), set);
+    }
+
+    #[test]
+    fn test_bad_json() {
+        assert!(matches!(parse_json_team("[{"), Err(ParseError::Json(_))));
+        assert!(matches!(
+            parse_json_team(r#"[{"item": "Leftovers"}]"#),
+            Err(ParseError::MissingName { .. })
+        ));
+        assert!(matches!(
+            parse_json_team(r#"[{"species": "Pikachu", "evs": {"atk": 253}}]"#),
+            Err(ParseError::OutOfRange { field: "EVs", .. })
+        ));
+        assert!(matches!(
+            parse_json_team(r#"[{"species": "Pikachu", "nature": "Sleepy"}]"#),
+            Err(ParseError::InvalidValue { field: "Nature", .. })
+        ));
+    }
+}
@@ -0,0 +1,140 @@
+/*
+* learnset.rs
+*
+* which moves each species can learn, so "Spore" on an Incineroar is caught
+* dex/learnsets.bin is written by dex_build/learnsets.js from @pkmn/dex
+*
+* the file is one entry per species, numbers are big endian:
+*   [species id u16][length of moves in bytes u16][moves]
+* moves are sorted by id, each one is a varint of
+*   (move id - previous move id) << 3 | generations
+* generations is a bit set: 1 = gen 7 or earlier, 2 = gen 8, 4 = gen 9
+* the gaps between sorted ids are small, so most moves take 1 or 2 bytes
+*
+* prevos and base formes are already merged in by the script
+* a species that isn't in the file has no learnset and its moves aren't
+* checked, while the file is still empty that's every species, build.rs
+* warns about it
+*/
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    dex::{DexId, Generation},
+    error::DexError,
+};
+
+const GEN7_OR_EARLIER: u8 = 1;
+const GEN8: u8 = 2;
+const GEN9: u8 = 4;
+const GENERATION_BITS: u32 = 3;
+
+// moves from older games count too, they come along when transferred
+// event only and transfer only restrictions aren't modelled
+fn learnable_in(generation: Generation) -> u8 {
+    match generation {
+        Generation::Gen7 => GEN7_OR_EARLIER,
+        Generation::Gen8 => GEN7_OR_EARLIER | GEN8,
+        Generation::Gen9 => GEN7_OR_EARLIER | GEN8 | GEN9,
+    }
+}
+
+// species -> the moves it can learn in generation
+pub fn parse_learnsets(
+    bytes: &[u8],
+    generation: Generation
+) -> Result<HashMap<DexId, HashSet<DexId>>, DexError> {
+    let mask = learnable_in(generation);
+    let mut learnsets = HashMap::new();
+    let mut rest = bytes;
+
+    while !rest.is_empty() {
+        let [s1, s2, l1, l2, tail @ ..] = rest else {
+            return Err(DexError::MalformedLearnsets);
+        };
+        let species = u16::from_be_bytes([*s1, *s2]);
+        let len = u16::from_be_bytes([*l1, *l2]) as usize;
+        if tail.len() < len {
+            return Err(DexError::MalformedLearnsets);
+        }
+        let (mut entries, tail) = tail.split_at(len);
+        rest = tail;
+
+        let mut moves = HashSet::new();
+        let mut move_id: u32 = 0;
+        while !entries.is_empty() {
+            let value = read_varint(&mut entries)?;
+            move_id += value >> GENERATION_BITS;
+            let id = DexId::try_from(move_id).map_err(|_| DexError::MalformedLearnsets)?;
+            if value as u8 & mask != 0 {
+                moves.insert(id);
+            }
+        }
+        learnsets.insert(species, moves);
+    }
+    Ok(learnsets)
+}
+
+// 7 bits at a time, lowest first, the high bit means more follow
+fn read_varint(bytes: &mut &[u8]) -> Result<u32, DexError> {
+    let mut value: u32 = 0;
+    for shift in (0..32).step_by(7) {
+        let (&byte, rest) = bytes.split_first().ok_or(DexError::MalformedLearnsets)?;
+        *bytes = rest;
+        value |= ((byte & 0x7f) as u32) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(DexError::MalformedLearnsets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the same thing dex_build/learnsets.js does
+    fn write(species: u16, moves: &[(u16, u8)]) -> Vec<u8> {
+        let mut body = Vec::new();
+        let mut previous = 0;
+        for &(id, gens) in moves {
+            let mut value = ((id - previous) as u32) << GENERATION_BITS | gens as u32;
+            while value >= 0x80 {
+                body.push((value & 0x7f) as u8 | 0x80);
+                value >>= 7;
+            }
+            body.push(value as u8);
+            previous = id;
+        }
+        let mut bytes = Vec::new();
+        bytes.extend(species.to_be_bytes());
+        bytes.extend((body.len() as u16).to_be_bytes());
+        bytes.extend(body);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_learnsets() {
+        let mut bytes = write(10, &[(1, GEN9), (400, GEN8 | GEN9), (901, GEN7_OR_EARLIER)]);
+        bytes.extend(write(11, &[]));
+
+        let gen9 = parse_learnsets(&bytes, Generation::Gen9).unwrap();
+        assert_eq!(gen9[&10], HashSet::from([1, 400, 901]));
+        assert!(gen9[&11].is_empty());
+
+        let gen8 = parse_learnsets(&bytes, Generation::Gen8).unwrap();
+        assert_eq!(gen8[&10], HashSet::from([400, 901]));
+        let gen7 = parse_learnsets(&bytes, Generation::Gen7).unwrap();
+        assert_eq!(gen7[&10], HashSet::from([901]));
+    }
+
+    #[test]
+    fn test_malformed_learnsets() {
+        let bytes = write(10, &[(1, GEN9), (900, GEN9)]);
+        assert!(parse_learnsets(&bytes[..bytes.len() - 1], Generation::Gen9).is_err());
+        assert!(parse_learnsets(&[0, 10, 0], Generation::Gen9).is_err());
+        // a varint that never ends
+        assert!(parse_learnsets(&[0, 10, 0, 1, 0x80], Generation::Gen9).is_err());
+        assert!(parse_learnsets(&[], Generation::Gen9).unwrap().is_empty());
+    }
+}
@@ -3,23 +3,48 @@
 */
 
 pub mod dex;
+pub mod diagnostic;
 pub mod parser;
 pub mod binary;
 pub mod codec;
+pub mod envelope;
 pub mod error;
+pub mod suggest;
+pub mod json;
+pub mod model;
+pub mod learnset;
+pub mod species;
+pub mod moves;
+pub mod typechart;
+pub mod stats;
+pub mod object;
+pub mod validate;
+pub mod analysis;
+pub mod damage;
+pub mod speed;
+pub mod optimise;
+pub mod export;
+
+use crate::dex::{Dex, Generation};
+use crate::parser::{Pokemon, Team};
+use crate::binary::{PokemonBin, TeamBin, RECORD_BYTES};
+use crate::validate::{Ruleset, Violation};
+use crate::error::{
+    ParseError,
+    DecodeError,
+    EncodeError,
+    DexError,
+    CalcError,
+    SpreadError,
+    PokepackError,
+};
 
-use crate::dex::Dex;
-use crate::parser::Pokemon;
-use crate::binary::PokemonBin;
-use crate::error::ParseError;
-
-use std::fmt::Write;
 use std::sync::OnceLock;
 
 use wasm_bindgen::prelude::wasm_bindgen;
 use wasm_bindgen::JsValue;
 use base64::prelude::*;
-use hex;
+use serde::Serialize;
 
 
 // error bridges
@@ -29,16 +54,70 @@ impl From<ParseError> for JsValue {
     }
 }
 
-// we only need one instance of the Dex 
-static POKEDEX: OnceLock<Dex> = OnceLock::new();
+impl From<DecodeError> for JsValue {
+    fn from(error: DecodeError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+impl From<EncodeError> for JsValue {
+    fn from(error: EncodeError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+impl From<DexError> for JsValue {
+    fn from(error: DexError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+impl From<CalcError> for JsValue {
+    fn from(error: CalcError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+impl From<SpreadError> for JsValue {
+    fn from(error: SpreadError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+impl From<PokepackError> for JsValue {
+    fn from(error: PokepackError) -> Self {
+        JsValue::from_str(&error.to_string())
+    }
+}
 
+// we only need one instance of the Dex per generation
+// the older gens are only built once someone asks for them
+static GEN7_DEX: OnceLock<Dex> = OnceLock::new();
+static GEN8_DEX: OnceLock<Dex> = OnceLock::new();
+static GEN9_DEX: OnceLock<Dex> = OnceLock::new();
+
+// the newest generation, which is what you get if you don't ask
 pub fn get_dex() -> &'static Dex {
+    get_dex_for(Generation::default())
+}
+
+pub fn get_dex_for(generation: Generation) -> &'static Dex {
+    let pokedex = match generation {
+        Generation::Gen7 => &GEN7_DEX,
+        Generation::Gen8 => &GEN8_DEX,
+        Generation::Gen9 => &GEN9_DEX,
+    };
     // return ref or init closure once
-    POKEDEX.get_or_init(|| {
-        println!("Building Pokédex for the first time...");
-        dex::Dex::build()
+    pokedex.get_or_init(|| {
+        println!("Building {} Pokédex for the first time...", generation);
+        // the dex is baked into the binary, a collision is a bug in dex/
+        dex::Dex::build(generation).unwrap_or_else(|e| panic!("{}", e))
     })
+}
 
+// wasm callers can leave the generation out, which means the newest
+fn generation_arg(generation: Option<u8>) -> Result<Generation, DexError> {
+    generation.map_or(Ok(Generation::default()), Generation::try_from)
 }
 
 #[wasm_bindgen(start)]
@@ -51,16 +130,64 @@ pub fn start() {
 // have to figure out best way for the library to be used
 // what is the most desired output?
 
-// helper
-pub fn pokepaste_to_pokepack(
-    pokepaste: String
-) -> Result<Vec<[u8; 21]>, ParseError> {
-    let dex = get_dex();
+// helpers
+pub fn pokepaste_to_pokemon_bin(
+    pokepaste: String,
+    generation: Generation
+) -> Result<Vec<PokemonBin>, PokepackError> {
+    let dex = get_dex_for(generation);
     // parse pokepaste into pokemon string struct
     let pokemon_strings: Vec<Pokemon> = parser::parse_pokepaste(pokepaste)?;
     // convert string to unpacked binary struct
+    Ok(codec::encode_all_pokemon(dex, pokemon_strings)?)
+}
+
+// same as above but unknown names become empty instead of failing
+pub fn pokepaste_to_pokemon_bin_lenient(
+    pokepaste: String,
+    generation: Generation
+) -> Result<(Vec<PokemonBin>, Vec<EncodeError>), ParseError> {
+    let dex = get_dex_for(generation);
+    let pokemon_strings: Vec<Pokemon> = parser::parse_pokepaste(pokepaste)?;
+    Ok(codec::encode_all_pokemon_lenient(dex, pokemon_strings))
+}
+
+// a team is always encoded against the dex of its own generation
+pub fn team_to_team_bin(team: Team) -> Result<TeamBin, EncodeError> {
+    codec::encode_team(get_dex_for(team.generation), team)
+}
+
+pub fn team_bin_to_team(team_bin: &TeamBin) -> Team {
+    codec::decode_team(get_dex_for(team_bin.generation), team_bin)
+}
+
+// a paste on its own is a team without a format or name
+pub fn pokepaste_to_team(
+    pokepaste: String,
+    generation: Generation
+) -> Result<Team, ParseError> {
+    Ok(Team {
+        generation,
+        pokemon: parser::parse_pokepaste(pokepaste)?,
+        ..Default::default()
+    })
+}
+
+fn pokepaste_to_team_bin(
+    pokepaste: String,
+    generation: Option<u8>
+) -> Result<TeamBin, PokepackError> {
+    let team = pokepaste_to_team(pokepaste, generation_arg(generation)?)?;
+    Ok(team_to_team_bin(team)?)
+}
+
+// just the fixed size records, nicknames are dropped
+// there is no header either, so these always mean gen 9
+pub fn pokepaste_to_pokepack(
+    pokepaste: String
+) -> Result<Vec<[u8; RECORD_BYTES]>, PokepackError> {
     let pokemon_bin: Vec<PokemonBin> = 
-        codec::encode_all_pokemon(&dex.maps, pokemon_strings);
+        pokepaste_to_pokemon_bin(pokepaste, Generation::Gen9)?;
     
     let packed_bytes = pokemon_bin
         .iter()
@@ -70,45 +197,350 @@ pub fn pokepaste_to_pokepack(
     Ok(packed_bytes)
 }
 
-// flat byte array
+// flat byte array, versioned header followed by the records
+// the generation ends up in the header, decoding reads it back from there
 #[wasm_bindgen]
-pub fn pokepaste_to_bytes(pokepaste: String) -> Result<Vec<u8>, JsValue> {
-    let packed_pokemon: Vec<[u8; 21]> = pokepaste_to_pokepack(pokepaste)?;
-    Ok(packed_pokemon.into_iter().flatten().collect())
+pub fn pokepaste_to_bytes(
+    pokepaste: String,
+    generation: Option<u8>
+) -> Result<Vec<u8>, JsValue> {
+    let team_bin = pokepaste_to_team_bin(pokepaste, generation)?;
+    Ok(envelope::to_bytes(&team_bin))
+}
+
+// same as above plus the format and team name
+// without a generation we go by the format, "gen8ou" is packed as gen 8
+#[wasm_bindgen]
+pub fn pokepaste_to_team_bytes(
+    pokepaste: String,
+    format: Option<String>,
+    name: Option<String>,
+    generation: Option<u8>
+) -> Result<Vec<u8>, JsValue> {
+    let format = format.unwrap_or_default();
+    let generation = match generation {
+        Some(generation) => Generation::try_from(generation)?,
+        None => Generation::from_format(&format)
+            .transpose()?
+            .unwrap_or_default(),
+    };
+    let mut team = pokepaste_to_team(pokepaste, generation)?;
+    team.format = format;
+    team.name = name.unwrap_or_default();
+    Ok(envelope::to_bytes(&team_to_team_bin(team)?))
+}
+
+// packed bytes plus whatever names we had to leave empty
+#[wasm_bindgen(getter_with_clone)]
+pub struct LenientBytes {
+    pub bytes: Vec<u8>,
+    pub warnings: Vec<String>,
+}
+
+#[wasm_bindgen]
+pub fn pokepaste_to_bytes_lenient(
+    pokepaste: String,
+    generation: Option<u8>
+) -> Result<LenientBytes, JsValue> {
+    let team = pokepaste_to_team(pokepaste, generation_arg(generation)?)?;
+    let dex = get_dex_for(team.generation);
+    let (team_bin, warnings) = codec::encode_team_lenient(dex, team)?;
+    Ok(LenientBytes {
+        bytes: envelope::to_bytes(&team_bin),
+        warnings: warnings.iter().map(|w| w.to_string()).collect(),
+    })
+}
+
+// everything the container knows about a team, the pokemon as a paste
+#[wasm_bindgen(getter_with_clone)]
+pub struct TeamPaste {
+    pub generation: u8,
+    pub format: String,
+    pub name: String,
+    pub paste: String,
+}
+
+#[wasm_bindgen]
+pub fn bytes_to_team(flat_byte_arr: Vec<u8>) -> Result<TeamPaste, JsValue> {
+    // header is checked and stripped here, it also tells us the generation
+    let team_bin = envelope::from_bytes(&flat_byte_arr)?;
+    let team = team_bin_to_team(&team_bin);
+    Ok(TeamPaste {
+        generation: team.generation.number(),
+        paste: team.to_string().trim().to_string(),
+        format: team.format,
+        name: team.name,
+    })
 }
 
 #[wasm_bindgen]
 pub fn bytes_to_pokepaste(flat_byte_arr: Vec<u8>) -> Result<String, JsValue> {
-    // must be multiple of 21 
-    if flat_byte_arr.len() % 21 != 0 {
-        // can expand on error message
-        return Err(JsValue::from_str("Invalid input length."));
-    }
+    Ok(bytes_to_team(flat_byte_arr)?.paste)
+}
+
+// the same text showdown's export button gives, see export.rs
+#[wasm_bindgen]
+pub fn bytes_to_showdown_export(flat_byte_arr: Vec<u8>) -> Result<String, JsValue> {
+    let (dex, team) = decode_pokemon(&flat_byte_arr)?;
+    Ok(export::export_team(dex, &team))
+}
+
+// showdown's packed team string, what the teambuilder keeps in localStorage
+// goes straight to our bytes and back, no paste in between
+#[wasm_bindgen]
+pub fn packed_team_to_bytes(
+    packed: String,
+    generation: Option<u8>
+) -> Result<Vec<u8>, JsValue> {
+    let team = Team {
+        generation: generation_arg(generation)?,
+        pokemon: parser::parse_packed_team(&packed)?,
+        ..Default::default()
+    };
+    Ok(envelope::to_bytes(&team_to_team_bin(team)?))
+}
+
+#[wasm_bindgen]
+pub fn bytes_to_packed_team(flat_byte_arr: Vec<u8>) -> Result<String, JsValue> {
+    let team_bin = envelope::from_bytes(&flat_byte_arr)?;
+    let team = team_bin_to_team(&team_bin);
+    Ok(parser::write_packed_team(&team.pokemon))
+}
+
 
+// a json array of showdown PokemonSet objects, see json.rs
+#[wasm_bindgen]
+pub fn json_to_bytes(
+    json: String,
+    generation: Option<u8>
+) -> Result<Vec<u8>, JsValue> {
+    let team = Team {
+        generation: generation_arg(generation)?,
+        pokemon: json::parse_json_team(&json)?,
+        ..Default::default()
+    };
+    Ok(envelope::to_bytes(&team_to_team_bin(team)?))
+}
+
+#[wasm_bindgen]
+pub fn bytes_to_json(flat_byte_arr: Vec<u8>) -> Result<String, JsValue> {
+    let team_bin = envelope::from_bytes(&flat_byte_arr)?;
+    let team = team_bin_to_team(&team_bin);
+    Ok(json::write_json_team(&team.pokemon))
+}
+
+
+// plain js objects instead of text, see object.rs for the shape
+// json_compatible so the flattened set comes out as an object, not a Map
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsValue> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(JsValue::from)
+}
+
+#[wasm_bindgen(unchecked_return_type = "PokemonObject[]")]
+pub fn decode_team(flat_byte_arr: Vec<u8>) -> Result<JsValue, JsValue> {
+    let team_bin = envelope::from_bytes(&flat_byte_arr)?;
+    let team = team_bin_to_team(&team_bin);
+    let dex = get_dex_for(team.generation);
+    to_js(&object::pokemon_objects(dex, team.pokemon))
+}
+
+// final stats for each pokemon of a packed team, see stats.rs
+// null for a species the dex has no base stats for
+#[wasm_bindgen(unchecked_return_type = "(StatsTable | null)[]")]
+pub fn decode_team_stats(flat_byte_arr: Vec<u8>) -> Result<JsValue, JsValue> {
+    let team_bin = envelope::from_bytes(&flat_byte_arr)?;
+    let dex = get_dex_for(team_bin.generation);
+    let stats: Vec<_> = team_bin
+        .pokemon
+        .iter()
+        .map(|pbin| stats::pokemon_bin_stats(dex, pbin))
+        .collect();
+    to_js(&stats)
+}
+
+// weaknesses and coverage of a packed team, see analysis.rs
+#[wasm_bindgen(unchecked_return_type = "TeamAnalysis")]
+pub fn decode_team_analysis(flat_byte_arr: Vec<u8>) -> Result<JsValue, JsValue> {
+    let (dex, team) = decode_pokemon(&flat_byte_arr)?;
+    to_js(&analysis::analyse_team(dex, &team))
+}
+
+// a packed team as sets, next to the dex for its generation
+fn decode_pokemon(bytes: &[u8]) -> Result<(&'static Dex, Vec<Pokemon>), DecodeError> {
+    let team_bin = envelope::from_bytes(bytes)?;
+    let dex = get_dex_for(team_bin.generation);
+    let team = team_bin
+        .pokemon
+        .iter()
+        .map(|pbin| codec::pokebin_to_string(dex, pbin))
+        .collect();
+    Ok((dex, team))
+}
+
+// speed tiers of a packed team, against another packed team as the meta
+// options is a SpeedOptions object, see speed.rs
+fn decode_speed(
+    flat_byte_arr: Vec<u8>,
+    meta: Option<Vec<u8>>,
+    options: JsValue
+) -> Result<speed::SpeedTiers, JsValue> {
+    let (dex, team) = decode_pokemon(&flat_byte_arr)?;
+    let meta = match meta {
+        Some(bytes) => decode_pokemon(&bytes)?.1,
+        None => Vec::new(),
+    };
+    let options: speed::SpeedOptions = if options.is_undefined() || options.is_null() {
+        speed::SpeedOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options)?
+    };
+    Ok(speed::speed_tiers(dex, &team, &meta, &options))
+}
+
+#[wasm_bindgen(unchecked_return_type = "SpeedTiers")]
+pub fn decode_speed_tiers(
+    flat_byte_arr: Vec<u8>,
+    meta: Option<Vec<u8>>,
+    #[wasm_bindgen(unchecked_param_type = "SpeedOptions | undefined")]
+    options: JsValue
+) -> Result<JsValue, JsValue> {
+    to_js(&decode_speed(flat_byte_arr, meta, options)?)
+}
+
+// the same as a plain text table
+#[wasm_bindgen]
+pub fn decode_speed_tier_table(
+    flat_byte_arr: Vec<u8>,
+    meta: Option<Vec<u8>>,
+    #[wasm_bindgen(unchecked_param_type = "SpeedOptions | undefined")]
+    options: JsValue
+) -> Result<String, JsValue> {
+    Ok(decode_speed(flat_byte_arr, meta, options)?.to_string())
+}
+
+// gen 9 damage from the first set of one paste to the first set of another
+// field is a Field object, see damage.rs, leave it out for singles
+#[wasm_bindgen(unchecked_return_type = "DamageResult")]
+pub fn calc_damage(
+    attacker: String,
+    defender: String,
+    move_name: String,
+    #[wasm_bindgen(unchecked_param_type = "Field | undefined")]
+    field: JsValue
+) -> Result<JsValue, JsValue> {
     let dex = get_dex();
-    let mut text = String::new();
+    let field: damage::Field = if field.is_undefined() || field.is_null() {
+        damage::Field::default()
+    } else {
+        serde_wasm_bindgen::from_value(field)?
+    };
+    let first_set = |paste: String, side: &str| -> Result<Pokemon, JsValue> {
+        parser::parse_pokepaste(paste)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| JsValue::from_str(&format!("No {} set in the paste.", side)))
+    };
+    let attacker = first_set(attacker, "attacker")?;
+    let defender = first_set(defender, "defender")?;
+    to_js(&damage::calc_damage(dex, &attacker, &defender, &move_name, &field)?)
+}
 
-    for chunk in flat_byte_arr.chunks_exact(21) {
-        let arr: [u8; 21] = chunk
-            .try_into()
-            .map_err(|e| {
-                JsValue::from_str(
-                    &format!("Internal error: failed to convert slice: {}", e))
-            })?;
-        let pbin = binary::unpack_from_bytes(&arr);
-        let s = codec::pokebin_to_string(&dex.tables, &pbin);
-        writeln!(&mut text, "{}", s)
-            .map_err(|e| JsValue::from_str(&e.to_string()))?;
-    }
+// the cheapest evs for the first set of the paste that meet every
+// benchmark, the rest go into dump ("SpA", "Atk"...) if one is given
+#[wasm_bindgen(unchecked_return_type = "SpreadResult")]
+pub fn optimise_spread(
+    pokepaste: String,
+    #[wasm_bindgen(unchecked_param_type = "Benchmark[]")]
+    benchmarks: JsValue,
+    dump: Option<String>
+) -> Result<JsValue, JsValue> {
+    let pokemon = parser::parse_pokepaste(pokepaste)?
+        .into_iter()
+        .next()
+        .ok_or(ParseError::EmptyBlock)?;
+    let inputs: Vec<optimise::BenchmarkInput> = serde_wasm_bindgen::from_value(benchmarks)?;
+    let benchmarks = inputs
+        .into_iter()
+        .map(optimise::Benchmark::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+    let dump = match dump {
+        Some(label) => Some(
+            model::Stat::from_label(&label)
+                .ok_or_else(|| JsValue::from_str(&format!("Unknown stat: {}", label)))?
+        ),
+        None => None,
+    };
+    let spread = optimise::optimise_spread(get_dex(), &pokemon, &benchmarks, dump)?;
+    to_js(&spread)
+}
+
+// ids are looked up without failing, unknown names just get null
+#[wasm_bindgen(unchecked_return_type = "PokemonObject[]")]
+pub fn parse_paste(
+    pokepaste: String,
+    generation: Option<u8>
+) -> Result<JsValue, JsValue> {
+    let dex = get_dex_for(generation_arg(generation)?);
+    let team = parser::parse_pokepaste(pokepaste)?;
+    to_js(&object::pokemon_objects(dex, team))
+}
 
-    Ok(text.trim().to_string())
+// everything wrong with a paste at once, next to what could still be read
+// only a bad generation is thrown, parse errors end up in diagnostics
+#[wasm_bindgen(unchecked_return_type = "PasteReport")]
+pub fn check_paste(
+    pokepaste: String,
+    generation: Option<u8>
+) -> Result<JsValue, JsValue> {
+    let dex = get_dex_for(generation_arg(generation)?);
+    let report = parser::check_pokepaste(&pokepaste);
+    to_js(&object::PasteReport::new(dex, report))
+}
+
+// legality, see validate.rs
+// empty means the team is fine, otherwise one message per problem
+pub fn validate_pokemon(
+    team: &[Pokemon],
+    format: &str
+) -> Result<Vec<Violation>, DexError> {
+    let rules = Ruleset::for_format(format)?;
+    Ok(validate::validate_team(get_dex_for(rules.generation), team, rules))
+}
+
+#[wasm_bindgen]
+pub fn validate_paste(
+    pokepaste: String,
+    format: String
+) -> Result<Vec<String>, JsValue> {
+    let team = parser::parse_pokepaste(pokepaste)?;
+    let violations = validate_pokemon(&team, &format)?;
+    Ok(violations.iter().map(|v| v.to_string()).collect())
+}
+
+// for teams that were already packed, without a format we use the one
+// in the header
+#[wasm_bindgen]
+pub fn validate_bytes(
+    flat_byte_arr: Vec<u8>,
+    format: Option<String>
+) -> Result<Vec<String>, JsValue> {
+    let team_bin = envelope::from_bytes(&flat_byte_arr)?;
+    let team = team_bin_to_team(&team_bin);
+    let format = format.unwrap_or(team.format);
+    let violations = validate_pokemon(&team.pokemon, &format)?;
+    Ok(violations.iter().map(|v| v.to_string()).collect())
 }
 
 
 // base64
 #[wasm_bindgen]
-pub fn pokepaste_to_base64(pokepaste: String) -> Result<String, JsValue> {
-    let packed_pokemon = pokepaste_to_pokepack(pokepaste)?;
+pub fn pokepaste_to_base64(
+    pokepaste: String,
+    generation: Option<u8>
+) -> Result<String, JsValue> {
+    let team_bin = pokepaste_to_team_bin(pokepaste, generation)?;
     /*
     let mut text = String::new();
     for p in packed_pokemon {
@@ -117,7 +549,8 @@ pub fn pokepaste_to_base64(pokepaste: String) -> Result<String, JsValue> {
     }
     Ok(text)
     */
-    let lines: Vec<String> = packed_pokemon
+    // header gets its own line
+    let lines: Vec<String> = envelope::to_chunks(&team_bin)
         .iter()
         .map(|p| BASE64_STANDARD.encode(p))
         .collect();
@@ -144,8 +577,11 @@ pub fn base64_to_pokepaste(b64: String) -> Result<String, JsValue> {
 
 // hex
 #[wasm_bindgen]
-pub fn pokepaste_to_hex(pokepaste: String) -> Result<String, JsValue> {
-    let packed_pokemon = pokepaste_to_pokepack(pokepaste)?;
+pub fn pokepaste_to_hex(
+    pokepaste: String,
+    generation: Option<u8>
+) -> Result<String, JsValue> {
+    let team_bin = pokepaste_to_team_bin(pokepaste, generation)?;
     /*
     let mut text = String::new();
     for p in packed_pokemon {
@@ -156,9 +592,9 @@ pub fn pokepaste_to_hex(pokepaste: String) -> Result<String, JsValue> {
     }
     Ok(text)
     */
-    let lines: Vec<String> = packed_pokemon
+    let lines: Vec<String> = envelope::to_chunks(&team_bin)
         .iter()
-        .map(|p| hex::encode(p))
+        .map(hex::encode)
         .collect();
 
     Ok(lines.join("\n"))
@@ -266,7 +702,7 @@ Jolly Nature
     fn test_bytes_conversion_roundtrip() {
         let paste = SAMPLE_PASTE.trim().to_string();
         
-        let bytes_encoded = pokepaste_to_bytes(paste.clone()).unwrap();
+        let bytes_encoded = pokepaste_to_bytes(paste.clone(), None).unwrap();
         let decoded_paste = bytes_to_pokepaste(bytes_encoded).unwrap();
 
         let original_structs = parser::parse_pokepaste(paste).unwrap();
@@ -278,7 +714,7 @@ Jolly Nature
     fn test_base64_conversion_roundtrip() {
         let paste = SAMPLE_PASTE.trim().to_string();
 
-        let base64_encoded = pokepaste_to_base64(paste.clone()).unwrap();
+        let base64_encoded = pokepaste_to_base64(paste.clone(), None).unwrap();
         let decoded_paste = base64_to_pokepaste(base64_encoded).unwrap();
         
         let original_structs = parser::parse_pokepaste(paste).unwrap();
@@ -290,12 +726,174 @@ Jolly Nature
     fn test_hex_conversion_roundtrip() {
         let paste = SAMPLE_PASTE.trim().to_string();
 
-        let hex_encoded = pokepaste_to_hex(paste.clone()).unwrap();
+        let hex_encoded = pokepaste_to_hex(paste.clone(), None).unwrap();
         let decoded_paste = hex_to_pokepaste(hex_encoded).unwrap();
 
         let original_structs = parser::parse_pokepaste(paste).unwrap();
         let decoded_structs = parser::parse_pokepaste(decoded_paste).unwrap();
         assert_eq!(original_structs, decoded_structs);
     }
+
+    #[test]
+    fn test_nickname_roundtrip() {
+        let paste = "Joe (Glimmora) (M) @ Focus Sash\nTera Type: Grass\n\n\
+                     Pikachu\nTera Type: Electric".to_string();
+
+        let bytes_encoded = pokepaste_to_bytes(paste.clone(), None).unwrap();
+        let decoded_paste = bytes_to_pokepaste(bytes_encoded).unwrap();
+        assert!(decoded_paste.starts_with("Joe (Glimmora) (M) @ Focus Sash"));
+
+        let original_structs = parser::parse_pokepaste(paste).unwrap();
+        let decoded_structs = parser::parse_pokepaste(decoded_paste).unwrap();
+        assert_eq!(original_structs, decoded_structs);
+    }
+
+    #[test]
+    fn test_aliases_decode_to_canonical_names() {
+        let paste = "Necrozma-DM @ Lefties\nTera Type: Steel\n- Vice Grip".to_string();
+        let bytes_encoded = pokepaste_to_bytes(paste, None).unwrap();
+        let decoded_paste = bytes_to_pokepaste(bytes_encoded).unwrap();
+        assert_eq!(
+            decoded_paste,
+            "Necrozma-Dusk-Mane @ Leftovers\nTera Type: Steel\n- Vise Grip"
+        );
+    }
+
+    #[test]
+    fn test_unknown_species_is_an_error() {
+        let paste = "Gholdngo @ Choice Specs\nTera Type: Steel".to_string();
+        let result = pokepaste_to_pokemon_bin(paste, Generation::Gen9);
+        assert!(matches!(
+            result,
+            Err(PokepackError::Encode(EncodeError::UnknownEntry { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_lenient_bytes_keep_warnings() {
+        let paste = "Gholdengo @ Choice Specs\n- Make It Rane".to_string();
+        let lenient = pokepaste_to_bytes_lenient(paste, None).unwrap();
+        assert_eq!(lenient.warnings, vec![
+            "Unknown move 'Make It Rane' in Pokémon #1. Did you mean: Make It Rain?"
+                .to_string()
+        ]);
+        let decoded_paste = bytes_to_pokepaste(lenient.bytes).unwrap();
+        assert_eq!(decoded_paste, "Gholdengo @ Choice Specs");
+    }
+
+    #[test]
+    fn test_generation_is_read_from_the_header() {
+        let paste = "Charizard-Gmax @ Heavy-Duty Boots\n\
+                     Dynamax Level: 3\nGigantamax: Yes\n- Flamethrower"
+            .to_string();
+        let bytes_encoded = pokepaste_to_bytes(paste.clone(), Some(8)).unwrap();
+        let decoded_paste = bytes_to_pokepaste(bytes_encoded).unwrap();
+        assert_eq!(decoded_paste, paste);
+
+        let paste = "Magnezone @ Firium Z\nHidden Power: Fire\n- Thunderbolt"
+            .to_string();
+        let b64 = pokepaste_to_base64(paste.clone(), Some(7)).unwrap();
+        assert_eq!(base64_to_pokepaste(b64).unwrap(), paste);
+    }
+
+    #[test]
+    fn test_team_metadata_roundtrip() {
+        let paste = "Corviknight @ Leftovers\n- Roost".to_string();
+        let bytes_encoded = pokepaste_to_team_bytes(
+            paste.clone(),
+            Some("gen8ou".into()),
+            Some("Bird Spam".into()),
+            None
+        ).unwrap();
+        let team = bytes_to_team(bytes_encoded).unwrap();
+        assert_eq!(team.generation, 8);
+        assert_eq!(team.format, "gen8ou");
+        assert_eq!(team.name, "Bird Spam");
+        assert_eq!(team.paste, paste);
+    }
+
+    #[test]
+    fn test_packed_team_roundtrip() {
+        let packed = "Joe|glimmora|focussash|toxicdebris|mortalspin,powergem|\
+                      Timid|,,4,252,,252|M||S|50|,,,,,Grass]\
+                      Iron Hands||assaultvest|quarkdrive|fakeout,drainpunch|\
+                      Adamant|252,252,,,4,||,,,,,0|||,,,,,Fire";
+        let bytes_encoded = packed_team_to_bytes(packed.to_string(), None).unwrap();
+        assert_eq!(bytes_to_packed_team(bytes_encoded.clone()).unwrap(), packed);
+
+        let paste = bytes_to_pokepaste(bytes_encoded).unwrap();
+        assert!(paste.starts_with("Joe (Glimmora) (M) @ Focus Sash"));
+    }
+
+    // a team the way the teambuilder keeps it, abilities as slots
+    // codec.rs checks the slots resolve, this only needs it to pack
+    #[test]
+    fn test_packed_ability_slots() {
+        let packed = "Incineroar||sitrusberry|H|fakeout,knockoff,flareblitz,partingshot|\
+                      Careful|252,4,,,252,|F|||50|,,,,,Ghost]\
+                      Rillaboom||assaultvest||fakeout,woodhammer,grassyglide,uturn|\
+                      Adamant|252,116,,,4,132|M|||50|,,,,,Fire";
+        let bytes_encoded = packed_team_to_bytes(packed.to_string(), None).unwrap();
+        let paste = bytes_to_pokepaste(bytes_encoded).unwrap();
+        assert!(paste.contains("Incineroar"));
+        assert!(paste.contains("Rillaboom"));
+        // a slot never leaks out as the ability's name
+        assert!(!paste.contains("Ability: H"));
+        assert!(!paste.contains("Ability: 0"));
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let json = r#"[{"name":"Iron Hands","species":"Iron Hands",
+            "item":"Assault Vest","ability":"Quark Drive",
+            "moves":["Fake Out","Drain Punch"],"nature":"Adamant","gender":"",
+            "evs":{"hp":252,"atk":252,"def":0,"spa":0,"spd":4,"spe":0},
+            "ivs":{"hp":31,"atk":31,"def":31,"spa":31,"spd":31,"spe":0},
+            "level":50,"teraType":"Fire"}]"#;
+        let bytes_encoded = json_to_bytes(json.to_string(), None).unwrap();
+        let decoded = bytes_to_json(bytes_encoded).unwrap();
+        assert_eq!(
+            json::parse_json_team(&decoded).unwrap(),
+            json::parse_json_team(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_generation_arg() {
+        assert_eq!(generation_arg(Some(1)), Err(DexError::UnsupportedGeneration {
+            generation: 1
+        }));
+        assert_eq!(generation_arg(None), Ok(Generation::Gen9));
+    }
+
+    #[test]
+    fn test_validate_by_format() {
+        // miraidon and lunala are both restricted
+        let team = parser::parse_pokepaste(SAMPLE_PASTE.to_string()).unwrap();
+        assert_eq!(
+            validate_pokemon(&team, "gen9vgc2024regg").unwrap(),
+            [Violation::TooManyRestricted { count: 2, max: 1 }]
+        );
+        assert!(matches!(
+            validate_pokemon(&team, "gen9randombattle"),
+            Err(DexError::UnsupportedFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn test_legacy_headerless_bytes_still_decode() {
+        let paste = SAMPLE_PASTE.trim().to_string();
+
+        let legacy: Vec<u8> = pokepaste_to_pokepack(paste.clone())
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect();
+        let decoded_paste = bytes_to_pokepaste(legacy).unwrap();
+
+        let original_structs = parser::parse_pokepaste(paste).unwrap();
+        let decoded_structs = parser::parse_pokepaste(decoded_paste).unwrap();
+        assert_eq!(original_structs, decoded_structs);
+    }
 }
 
@@ -20,12 +20,12 @@ fn tests() {
     println!();
     
     // skip rn
-    //let output_hex = pokepack::pokepaste_to_hex(testpaste.into());
+    //let output_hex = pokepack::pokepaste_to_hex(testpaste.into(), None);
     //println!("Hex:\n{}", &output_hex);
     //let h = pokepack::hex_to_pokepaste(output_hex).unwrap();
     //println!("Hex Conversion:\n{}", &h);
 
-    let output_b64 = pokepack::pokepaste_to_base64(testpaste.into()).unwrap();
+    let output_b64 = pokepack::pokepaste_to_base64(testpaste.into(), None).unwrap();
     println!("Base64:\n{}", &output_b64);
     let s = pokepack::base64_to_pokepaste(output_b64).unwrap();
     println!("Base64 Conversion:\n\n{}", &s);
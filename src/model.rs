@@ -0,0 +1,534 @@
+/*
+* model.rs
+*
+* the typed pieces of a pokemon set, see parser::Pokemon
+* everything here is checked once while parsing, so the codec never has
+* to guess what a level of "300" or "abc" was supposed to be
+*
+* species, items, abilities and moves stay names (moves as a MoveId), only
+* the dex for the team's generation knows whether they exist, codec.rs
+* looks them up
+*/
+
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    ops::{Index, IndexMut},
+    str::FromStr,
+};
+
+use serde::Serialize;
+
+use crate::{dex, error::ParseError};
+
+// limits showdown's teambuilder enforces
+pub const MIN_LEVEL: u8 = 1;
+pub const MAX_LEVEL: u8 = 100;
+pub const MAX_EV: u8 = 252;
+pub const MAX_IV: u8 = 31;
+pub const MAX_DYNAMAX_LEVEL: u8 = 10;
+// also what no happiness means
+pub const MAX_HAPPINESS: u8 = 255;
+pub const MAX_MOVES: usize = 4;
+
+// genderless pokemon (and sets that don't say) have no gender at all
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Gender {
+    Male,
+    Female,
+}
+
+impl Gender {
+    // showdown's "M" and "F"
+    pub fn letter(self) -> &'static str {
+        match self {
+            Gender::Male => "M",
+            Gender::Female => "F",
+        }
+    }
+
+    // "" and "N" (genderless) are no gender, anything else is an error
+    pub fn parse(text: &str) -> Result<Option<Self>, ParseError> {
+        match text.trim().to_uppercase().as_str() {
+            "" | "N" => Ok(None),
+            "M" => Ok(Some(Gender::Male)),
+            "F" => Ok(Some(Gender::Female)),
+            _ => Err(ParseError::InvalidValue {
+                field: "Gender",
+                value: text.to_string(),
+                span: None,
+            }),
+        }
+    }
+}
+
+// one value per stat, used for EVs and IVs, and final stats (stats.rs)
+// serializes like showdown's StatsTable
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct Stats<T> {
+    pub hp:     T,
+    pub atk:    T,
+    pub def:    T,
+    pub spa:    T,
+    pub spd:    T,
+    pub spe:    T,
+}
+
+// how showdown labels the stats in a paste, same order as to_array
+pub const STAT_LABELS: [&str; 6] = ["HP", "Atk", "Def", "SpA", "SpD", "Spe"];
+
+// same order as to_array
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stat {
+    Hp, Atk, Def, Spa, Spd, Spe,
+}
+
+impl Stat {
+    pub const ALL: [Stat; 6] = [Stat::Hp, Stat::Atk, Stat::Def, Stat::Spa, Stat::Spd, Stat::Spe];
+
+    pub fn label(self) -> &'static str {
+        STAT_LABELS[self as usize]
+    }
+
+    // by paste label, "spa" or "SpA"
+    pub fn from_label(label: &str) -> Option<Self> {
+        Stat::ALL.into_iter().find(|stat| stat.label().eq_ignore_ascii_case(label))
+    }
+}
+
+impl<T> Index<Stat> for Stats<T> {
+    type Output = T;
+
+    fn index(&self, stat: Stat) -> &T {
+        match stat {
+            Stat::Hp    => &self.hp,
+            Stat::Atk   => &self.atk,
+            Stat::Def   => &self.def,
+            Stat::Spa   => &self.spa,
+            Stat::Spd   => &self.spd,
+            Stat::Spe   => &self.spe,
+        }
+    }
+}
+
+impl<T> IndexMut<Stat> for Stats<T> {
+    fn index_mut(&mut self, stat: Stat) -> &mut T {
+        match stat {
+            Stat::Hp    => &mut self.hp,
+            Stat::Atk   => &mut self.atk,
+            Stat::Def   => &mut self.def,
+            Stat::Spa   => &mut self.spa,
+            Stat::Spd   => &mut self.spd,
+            Stat::Spe   => &mut self.spe,
+        }
+    }
+}
+
+impl<T: Copy> Stats<T> {
+    pub const fn splat(value: T) -> Self {
+        Stats {
+            hp:     value,
+            atk:    value,
+            def:    value,
+            spa:    value,
+            spd:    value,
+            spe:    value,
+        }
+    }
+
+    // hp, atk, def, spa, spd, spe
+    pub fn to_array(self) -> [T; 6] {
+        [self.hp, self.atk, self.def, self.spa, self.spd, self.spe]
+    }
+
+    pub fn from_array(values: [T; 6]) -> Self {
+        let [hp, atk, def, spa, spd, spe] = values;
+        Stats { hp, atk, def, spa, spd, spe }
+    }
+
+    // by paste label, "spa" or "SpA"
+    pub fn get_mut(&mut self, label: &str) -> Option<&mut T> {
+        match label.to_lowercase().as_str() {
+            "hp"    => Some(&mut self.hp),
+            "atk"   => Some(&mut self.atk),
+            "def"   => Some(&mut self.def),
+            "spa"   => Some(&mut self.spa),
+            "spd"   => Some(&mut self.spd),
+            "spe"   => Some(&mut self.spe),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Nature {
+    Bashful, Docile, Hardy, Quirky, Serious,
+    Adamant, Brave, Lonely, Naughty,
+    Bold, Impish, Lax, Relaxed,
+    Modest, Mild, Quiet, Rash,
+    Calm, Careful, Gentle, Sassy,
+    Hasty, Jolly, Naive, Timid,
+}
+
+impl Nature {
+    // same order as the enum, and as dex/natures.txt
+    pub const ALL: [Nature; 25] = [
+        Nature::Bashful, Nature::Docile, Nature::Hardy, Nature::Quirky,
+        Nature::Serious, Nature::Adamant, Nature::Brave, Nature::Lonely,
+        Nature::Naughty, Nature::Bold, Nature::Impish, Nature::Lax,
+        Nature::Relaxed, Nature::Modest, Nature::Mild, Nature::Quiet,
+        Nature::Rash, Nature::Calm, Nature::Careful, Nature::Gentle,
+        Nature::Sassy, Nature::Hasty, Nature::Jolly, Nature::Naive,
+        Nature::Timid,
+    ];
+
+    const NAMES: [&'static str; 25] = [
+        "Bashful", "Docile", "Hardy", "Quirky", "Serious",
+        "Adamant", "Brave", "Lonely", "Naughty",
+        "Bold", "Impish", "Lax", "Relaxed",
+        "Modest", "Mild", "Quiet", "Rash",
+        "Calm", "Careful", "Gentle", "Sassy",
+        "Hasty", "Jolly", "Naive", "Timid",
+    ];
+
+    pub fn name(self) -> &'static str {
+        Nature::NAMES[self as usize]
+    }
+
+    // the stat it raises by 10% and the one it lowers by 10%
+    // None for the five that do nothing
+    pub fn effect(self) -> Option<(Stat, Stat)> {
+        use Stat::*;
+        let effect = match self {
+            Nature::Bashful | Nature::Docile | Nature::Hardy
+            | Nature::Quirky | Nature::Serious => return None,
+            Nature::Adamant => (Atk, Spa),
+            Nature::Brave   => (Atk, Spe),
+            Nature::Lonely  => (Atk, Def),
+            Nature::Naughty => (Atk, Spd),
+            Nature::Bold    => (Def, Atk),
+            Nature::Impish  => (Def, Spa),
+            Nature::Lax     => (Def, Spd),
+            Nature::Relaxed => (Def, Spe),
+            Nature::Modest  => (Spa, Atk),
+            Nature::Mild    => (Spa, Def),
+            Nature::Quiet   => (Spa, Spe),
+            Nature::Rash    => (Spa, Spd),
+            Nature::Calm    => (Spd, Atk),
+            Nature::Careful => (Spd, Spa),
+            Nature::Gentle  => (Spd, Def),
+            Nature::Sassy   => (Spd, Spe),
+            Nature::Hasty   => (Spe, Def),
+            Nature::Jolly   => (Spe, Spa),
+            Nature::Naive   => (Spe, Spd),
+            Nature::Timid   => (Spe, Atk),
+        };
+        Some(effect)
+    }
+}
+
+impl fmt::Display for Nature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Nature {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        find_by_name(&Nature::ALL, &Nature::NAMES, text, "Nature")
+    }
+}
+
+// tera types, and the types hidden power can be
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Type {
+    Normal, Fire, Fighting, Water, Flying, Grass,
+    Poison, Electric, Ground, Psychic, Rock, Ice,
+    Bug, Dragon, Ghost, Dark, Steel, Fairy,
+    // tera only
+    Stellar,
+}
+
+impl Type {
+    // same order as the enum, and as dex/teras.txt
+    pub const ALL: [Type; 19] = [
+        Type::Normal, Type::Fire, Type::Fighting, Type::Water, Type::Flying,
+        Type::Grass, Type::Poison, Type::Electric, Type::Ground,
+        Type::Psychic, Type::Rock, Type::Ice, Type::Bug, Type::Dragon,
+        Type::Ghost, Type::Dark, Type::Steel, Type::Fairy, Type::Stellar,
+    ];
+
+    const NAMES: [&'static str; 19] = [
+        "Normal", "Fire", "Fighting", "Water", "Flying", "Grass",
+        "Poison", "Electric", "Ground", "Psychic", "Rock", "Ice",
+        "Bug", "Dragon", "Ghost", "Dark", "Steel", "Fairy",
+        "Stellar",
+    ];
+
+    pub fn name(self) -> &'static str {
+        Type::NAMES[self as usize]
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// by name, so json gets "Fire" not 0
+impl Serialize for Type {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl FromStr for Type {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        find_by_name(&Type::ALL, &Type::NAMES, text, "Type")
+    }
+}
+
+// a move as the set names it, compared by its showdown id,
+// so "U-turn", "u turn" and "uturn" are the same move
+// the spelling it was given is kept for printing, whether the move
+// exists is up to the dex (see model.rs header)
+#[derive(Debug, Clone)]
+pub struct MoveId {
+    name:   String,
+    id:     String,
+}
+
+impl MoveId {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl PartialEq for MoveId {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for MoveId {}
+
+impl Hash for MoveId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+// so tests can compare against plain names
+impl PartialEq<&str> for MoveId {
+    fn eq(&self, other: &&str) -> bool {
+        self.id == dex::to_id(other)
+    }
+}
+
+impl AsRef<str> for MoveId {
+    fn as_ref(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Display for MoveId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+// a name without a single letter or digit can't be any move
+impl FromStr for MoveId {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let name = text.trim();
+        let id = dex::to_id(name);
+        if id.is_empty() {
+            return Err(ParseError::InvalidValue {
+                field: "Move",
+                value: text.to_string(),
+                span: None,
+            });
+        }
+        Ok(MoveId { name: name.to_string(), id })
+    }
+}
+
+// compared as showdown ids, so "timid", "TIMID" and "Timid" all work
+fn find_by_name<T: Copy>(
+    all: &[T],
+    names: &[&str],
+    text: &str,
+    field: &'static str
+) -> Result<T, ParseError> {
+    let id = dex::to_id(text);
+    names
+        .iter()
+        .position(|name| dex::to_id(name) == id)
+        .map(|i| all[i])
+        .ok_or_else(|| ParseError::InvalidValue {
+            field,
+            value: text.to_string(),
+            span: None,
+        })
+}
+
+// "" means the field wasn't given
+pub fn parse_optional<T: FromStr<Err = ParseError>>(
+    text: &str
+) -> Result<Option<T>, ParseError> {
+    let text = text.trim();
+    if text.is_empty() {
+        Ok(None)
+    } else {
+        text.parse().map(Some)
+    }
+}
+
+pub fn check_range(
+    field: &'static str,
+    value: u8,
+    min: u8,
+    max: u8
+) -> Result<u8, ParseError> {
+    if (min..=max).contains(&value) {
+        Ok(value)
+    } else {
+        Err(ParseError::OutOfRange {
+            field,
+            value: value.to_string(),
+            min,
+            max,
+            span: None,
+        })
+    }
+}
+
+// a number that has to be in min..=max
+// too big for a u8 is still out of range, not garbage
+pub fn parse_number(
+    field: &'static str,
+    text: &str,
+    min: u8,
+    max: u8
+) -> Result<u8, ParseError> {
+    let text = text.trim();
+    match text.parse::<u64>() {
+        Ok(value) if value <= u8::MAX as u64 => check_range(field, value as u8, min, max),
+        Ok(_) => Err(ParseError::OutOfRange {
+            field,
+            value: text.to_string(),
+            min,
+            max,
+            span: None,
+        }),
+        Err(_) => Err(ParseError::InvalidValue {
+            field,
+            value: text.to_string(),
+            span: None,
+        }),
+    }
+}
+
+// "Yes" and "No", how showdown writes Shiny and Gigantamax
+pub fn parse_yes_no(field: &'static str, text: &str) -> Result<bool, ParseError> {
+    match text.trim().to_lowercase().as_str() {
+        "yes" => Ok(true),
+        "no" | "" => Ok(false),
+        _ => Err(ParseError::InvalidValue {
+            field,
+            value: text.to_string(),
+            span: None,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_match_the_dex() {
+        let dex = crate::get_dex();
+        for nature in Nature::ALL {
+            assert!(dex.maps.natures.contains_key(&dex::to_id(nature.name())));
+        }
+        for t in Type::ALL {
+            assert!(dex.maps.teras.contains_key(&dex::to_id(t.name())));
+        }
+        assert_eq!("TIMID".parse::<Nature>().unwrap(), Nature::Timid);
+        assert_eq!("stellar".parse::<Type>().unwrap(), Type::Stellar);
+        assert!(matches!(
+            "Sleepy".parse::<Nature>(),
+            Err(ParseError::InvalidValue { field: "Nature", .. })
+        ));
+    }
+
+    // every stat but hp is raised by four natures and lowered by four
+    #[test]
+    fn test_nature_effects() {
+        let effects: Vec<(Stat, Stat)> = Nature::ALL
+            .into_iter()
+            .filter_map(Nature::effect)
+            .collect();
+        assert_eq!(effects.len(), 20);
+        for stat in &Stat::ALL[1..] {
+            assert_eq!(effects.iter().filter(|(plus, _)| plus == stat).count(), 4);
+            assert_eq!(effects.iter().filter(|(_, minus)| minus == stat).count(), 4);
+        }
+        assert!(effects.iter().all(|(plus, minus)| plus != minus));
+        assert_eq!(Nature::Timid.effect(), Some((Stat::Spe, Stat::Atk)));
+        assert_eq!(Stat::Spa.label(), "SpA");
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("Level", " 50 ", 1, 100).unwrap(), 50);
+        assert!(matches!(
+            parse_number("EVs", "300", 0, MAX_EV),
+            Err(ParseError::OutOfRange { field: "EVs", max: 252, .. })
+        ));
+        assert!(matches!(
+            parse_number("EVs", "100000", 0, MAX_EV),
+            Err(ParseError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            parse_number("Level", "abc", 1, 100),
+            Err(ParseError::InvalidValue { field: "Level", .. })
+        ));
+        assert!(parse_number("Level", "0", MIN_LEVEL, MAX_LEVEL).is_err());
+    }
+
+    #[test]
+    fn test_move_id() {
+        let uturn: MoveId = " U-turn ".parse().unwrap();
+        assert_eq!(uturn.name(), "U-turn");
+        assert_eq!(uturn.id(), "uturn");
+        assert_eq!(uturn, "u turn".parse::<MoveId>().unwrap());
+        assert_eq!(uturn, "UTURN");
+        assert!(matches!(
+            "???".parse::<MoveId>(),
+            Err(ParseError::InvalidValue { field: "Move", .. })
+        ));
+    }
+
+    #[test]
+    fn test_gender_and_yes_no() {
+        assert_eq!(Gender::parse("m").unwrap(), Some(Gender::Male));
+        assert_eq!(Gender::parse("N").unwrap(), None);
+        assert!(Gender::parse("X").is_err());
+        assert!(parse_yes_no("Shiny", "YES").unwrap());
+        assert!(!parse_yes_no("Shiny", "No").unwrap());
+        assert!(parse_yes_no("Shiny", "maybe").is_err());
+    }
+}
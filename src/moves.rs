@@ -0,0 +1,131 @@
+/*
+* moves.rs
+*
+* what the dex knows about a move besides its name
+* dex/movedata.txt is written by dex_build/moves.js from @pkmn/dex,
+* one move per line, keyed by the same id as dex/moves.txt:
+*
+*   ID<TAB>Type<TAB>Category<TAB>Base Power<TAB>Target
+*   85	Electric	Special	90	normal
+*
+* base power is 0 for status moves and for moves whose power isn't fixed
+* (low kick, seismic toss), target is showdown's, we only care whether it
+* hits more than one pokemon
+*
+* a move that isn't in the file just has no data, nothing fails on that
+*/
+
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use serde::Serialize;
+
+use crate::{
+    dex::DexId,
+    error::DexError,
+    model::Type,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum Category {
+    Physical,
+    Special,
+    Status,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Category::Physical  => "Physical",
+            Category::Special   => "Special",
+            Category::Status    => "Status",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for Category {
+    type Err = ();
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "Physical"  => Ok(Category::Physical),
+            "Special"   => Ok(Category::Special),
+            "Status"    => Ok(Category::Status),
+            _ => Err(()),
+        }
+    }
+}
+
+// showdown targets that hit every adjacent foe, or everyone adjacent
+const SPREAD_TARGETS: [&str; 2] = ["allAdjacentFoes", "allAdjacent"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveData {
+    pub move_type:  Type,
+    pub category:   Category,
+    pub base_power: u16,
+    // true when it hits more than one target in doubles
+    pub spread:     bool,
+}
+
+impl MoveData {
+    pub fn is_damaging(&self) -> bool {
+        self.category != Category::Status
+    }
+}
+
+pub fn parse_moves(file: &str) -> Result<HashMap<DexId, MoveData>, DexError> {
+    let mut moves = HashMap::new();
+    for line in file.lines().filter(|line| !line.trim().is_empty()) {
+        let (id, data) = parse_line(line)
+            .ok_or_else(|| DexError::MalformedMove { line: line.to_string() })?;
+        moves.insert(id, data);
+    }
+    Ok(moves)
+}
+
+fn parse_line(line: &str) -> Option<(DexId, MoveData)> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    let [id, move_type, category, base_power, target] = fields[..] else {
+        return None;
+    };
+    if target.is_empty() {
+        return None;
+    }
+    Some((id.parse().ok()?, MoveData {
+        move_type:  move_type.parse().ok()?,
+        category:   category.parse().ok()?,
+        base_power: base_power.parse().ok()?,
+        spread:     SPREAD_TARGETS.contains(&target),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_moves() {
+        let file = "85\tElectric\tSpecial\t90\tnormal\n\
+                    14\tNormal\tStatus\t0\tself\n\
+                    89\tGround\tPhysical\t100\tallAdjacent\n";
+        let moves = parse_moves(file).unwrap();
+        assert_eq!(moves[&85].move_type, Type::Electric);
+        assert_eq!(moves[&85].base_power, 90);
+        assert!(moves[&85].is_damaging());
+        assert!(!moves[&85].spread);
+        assert!(!moves[&14].is_damaging());
+        assert!(moves[&89].spread);
+
+        let bad = [
+            "85\tElectric\tSpecial\t90",
+            "85\tSound\tSpecial\t90\tnormal",
+            "85\tElectric\tspecial\t90\tnormal",
+            "85\tElectric\tSpecial\t-90\tnormal",
+            "85\tElectric\tSpecial\t90\t",
+        ];
+        for bad in bad {
+            assert!(matches!(parse_moves(bad), Err(DexError::MalformedMove { .. })));
+        }
+    }
+}
@@ -0,0 +1,205 @@
+/*
+* object.rs
+*
+* plain objects for the js side, so the front end doesn't have to parse
+* paste text again just to find an item icon
+* a PokemonObject is a showdown PokemonSet (see json.rs) plus the dex ids
+* we would pack each name as, null when the name isn't in the dex
+*
+* a PasteReport is the same for a paste that only partly parsed, with
+* every error and warning next to it, see parser::check_pokepaste
+*
+* lib.rs hands these to serde_wasm_bindgen, the matching typescript
+* is written out below and ends up in the generated .d.ts
+*/
+
+use serde::Serialize;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    dex::{self, Dex, DexId, EntryKind, Generation},
+    diagnostic::Diagnostic,
+    json::PokemonSet,
+    model::{Nature, Type},
+    parser::{ParseReport, Pokemon},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PokemonObject {
+    #[serde(flatten)]
+    pub set: PokemonSet,
+    pub ids: DexIds,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DexIds {
+    pub species:    Option<DexId>,
+    pub item:       Option<DexId>,
+    pub ability:    Option<DexId>,
+    pub nature:     Option<DexId>,
+    // only gen 9 packs a tera type
+    pub tera_type:  Option<DexId>,
+    pub moves:      Vec<Option<DexId>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PasteReport {
+    pub pokemon:        Vec<PokemonObject>,
+    pub diagnostics:    Vec<Diagnostic>,
+}
+
+// keep in sync with PokemonSet, DexIds and PasteReport
+// Diagnostic is in diagnostic.rs
+#[wasm_bindgen(typescript_custom_section)]
+const TYPESCRIPT: &str = r#"
+export interface StatsTable {
+    hp: number;
+    atk: number;
+    def: number;
+    spa: number;
+    spd: number;
+    spe: number;
+}
+
+export interface DexIds {
+    species: number | null;
+    item: number | null;
+    ability: number | null;
+    nature: number | null;
+    teraType: number | null;
+    moves: (number | null)[];
+}
+
+export interface PokemonObject {
+    name: string;
+    species: string;
+    item: string;
+    ability: string;
+    moves: string[];
+    nature: string;
+    gender: "M" | "F" | "";
+    evs: StatsTable;
+    ivs: StatsTable;
+    level?: number;
+    shiny?: boolean;
+    happiness?: number;
+    hpType?: string;
+    dynamaxLevel?: number;
+    gigantamax?: boolean;
+    teraType?: string;
+    ids: DexIds;
+}
+
+export interface PasteReport {
+    pokemon: PokemonObject[];
+    diagnostics: Diagnostic[];
+}
+"#;
+
+// empty names have no id, the same as leaving them out of the object
+fn lookup(dex: &Dex, kind: EntryKind, name: &str) -> Option<DexId> {
+    if name.is_empty() {
+        return None;
+    }
+    dex.maps.get(kind).get(&dex::to_id(name)).copied()
+}
+
+impl DexIds {
+    pub fn new(dex: &Dex, pokemon: &Pokemon) -> Self {
+        let tera_type = match dex.generation {
+            Generation::Gen9 => {
+                let tera = pokemon.tera.map(Type::name).unwrap_or_default();
+                lookup(dex, EntryKind::Tera, tera)
+            },
+            _ => None,
+        };
+        let nature = pokemon.nature.map(Nature::name).unwrap_or_default();
+        let ability = dex.resolve_ability(&pokemon.name, &pokemon.ability);
+        DexIds {
+            species:    lookup(dex, EntryKind::Species, &pokemon.name),
+            item:       lookup(dex, EntryKind::Item, &pokemon.item),
+            ability:    lookup(dex, EntryKind::Ability, ability),
+            nature:     lookup(dex, EntryKind::Nature, nature),
+            tera_type,
+            moves:      pokemon
+                .moves
+                .iter()
+                .map(|m| lookup(dex, EntryKind::Move, m.name()))
+                .collect(),
+        }
+    }
+}
+
+impl PokemonObject {
+    pub fn new(dex: &Dex, pokemon: Pokemon) -> Self {
+        PokemonObject {
+            ids: DexIds::new(dex, &pokemon),
+            set: pokemon.into(),
+        }
+    }
+}
+
+pub fn pokemon_objects(dex: &Dex, team: Vec<Pokemon>) -> Vec<PokemonObject> {
+    team
+        .into_iter()
+        .map(|p| PokemonObject::new(dex, p))
+        .collect()
+}
+
+impl PasteReport {
+    pub fn new(dex: &Dex, report: ParseReport) -> Self {
+        PasteReport {
+            diagnostics:    report.diagnostics(),
+            pokemon:        pokemon_objects(dex, report.pokemon),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn test_objects_carry_dex_ids() {
+        let dex = crate::get_dex();
+        let team = parser::parse_pokepaste(
+            "Gholdengo @ Choice Specs\nTera Type: Steel\n- Make It Rain\n- Trik"
+                .to_string()
+        ).unwrap();
+        let objects = pokemon_objects(dex, team);
+        let ids = &objects[0].ids;
+        assert_eq!(ids.species, dex.maps.names.get("gholdengo").copied());
+        assert_eq!(ids.item, dex.maps.items.get("choicespecs").copied());
+        assert_eq!(ids.tera_type, dex.maps.teras.get("steel").copied());
+        assert_eq!(ids.ability, None);
+        assert_eq!(ids.moves.len(), 2);
+        assert!(ids.moves[0].is_some());
+        assert_eq!(ids.moves[1], None);
+    }
+
+    #[test]
+    fn test_object_shape() {
+        let dex = crate::get_dex();
+        let team = parser::parse_pokepaste("Pikachu".to_string()).unwrap();
+        let json = serde_json::to_value(pokemon_objects(dex, team)).unwrap();
+        assert_eq!(json[0]["species"], "pikachu");
+        assert!(json[0].get("level").is_none());
+        assert_eq!(json[0]["ivs"]["spe"], 31);
+        assert!(json[0]["ids"]["species"].is_number());
+        assert!(json[0]["ids"]["teraType"].is_null());
+    }
+
+    #[test]
+    fn test_report_shape() {
+        let dex = crate::get_dex();
+        let paste = "Pikachu\nLevel: 300\nShiny: No";
+        let report = PasteReport::new(dex, parser::check_pokepaste(paste));
+        let json = serde_json::to_value(report).unwrap();
+        assert_eq!(json["pokemon"][0]["species"], "pikachu");
+        assert_eq!(json["diagnostics"][0]["severity"], "error");
+        assert_eq!(json["diagnostics"][0]["span"]["line"], 2);
+        assert_eq!(json["diagnostics"][1]["severity"], "warning");
+    }
+}
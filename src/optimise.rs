@@ -0,0 +1,387 @@
+/*
+* optimise.rs
+*
+* "survive this, outspeed that, dump the rest": finds the cheapest EV
+* spread that meets every benchmark, so as many EVs as possible are left
+* over for the dump stat
+* nature, IVs and level stay what the set has, EVs go up in steps of 4
+*
+* speed only depends on speed EVs, so it's the smallest amount that clears
+* every speed benchmark
+* bulk tries every HP and Def split, and for each one looks for the
+* smallest SpD that survives every hit (more SpD never hurts, so it's a
+* binary search), keeping whichever split costs the least
+*/
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    damage::{self, Field},
+    dex::Dex,
+    error::{CalcError, ParseError, SpreadError},
+    model::{Stat, Stats, MAX_EV},
+    parser::{self, Pokemon},
+    speed::{self, SpeedItem},
+    stats,
+    validate::MAX_EV_TOTAL,
+};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPESCRIPT: &str = r#"
+export type Benchmark =
+    | { kind: "outspeed"; speed: number }
+    | { kind: "survive"; attacker: string; move: string; field?: Field; hits?: number };
+
+export interface SpreadResult {
+    evs: StatsTable;
+    stats: StatsTable;
+    leftover: number;
+}
+"#;
+
+// evs only count in steps of 4
+const EV_STEP: u8 = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Benchmark {
+    // be faster than this, counting the set's own scarf or booster energy
+    Outspeed { speed: u16 },
+    // take this many hits of the attack's highest roll and live
+    Survive { attacker: Box<Pokemon>, move_name: String, field: Field, hits: u8 },
+}
+
+impl Benchmark {
+    // one hit, singles
+    pub fn survive(attacker: Pokemon, move_name: &str) -> Self {
+        Benchmark::Survive {
+            attacker: Box::new(attacker),
+            move_name: move_name.to_string(),
+            field: Field::default(),
+            hits: 1,
+        }
+    }
+}
+
+// a benchmark the way js hands it over, the attacker is a paste
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum BenchmarkInput {
+    Outspeed { speed: u16 },
+    Survive {
+        attacker: String,
+        #[serde(rename = "move")]
+        move_name: String,
+        #[serde(default)]
+        field: Field,
+        #[serde(default = "one_hit")]
+        hits: u8,
+    },
+}
+
+fn one_hit() -> u8 {
+    1
+}
+
+impl TryFrom<BenchmarkInput> for Benchmark {
+    type Error = ParseError;
+
+    // the first set of the attacker's paste
+    fn try_from(input: BenchmarkInput) -> Result<Self, Self::Error> {
+        Ok(match input {
+            BenchmarkInput::Outspeed { speed } => Benchmark::Outspeed { speed },
+            BenchmarkInput::Survive { attacker, move_name, field, hits } => {
+                let attacker = parser::parse_pokepaste(attacker)?
+                    .into_iter()
+                    .next()
+                    .ok_or(ParseError::EmptyBlock)?;
+                Benchmark::Survive { attacker: Box::new(attacker), move_name, field, hits }
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SpreadResult {
+    pub evs:        Stats<u8>,
+    // the set's stats with these evs
+    pub stats:      Stats<u16>,
+    // evs the benchmarks didn't need, counted before the dump stat got any
+    pub leftover:   u16,
+}
+
+// every ev amount we try for one stat, 0 to 252
+fn ev_steps(max: u8) -> impl DoubleEndedIterator<Item = u8> {
+    (0..=max.min(MAX_EV)).step_by(EV_STEP as usize)
+}
+
+// the evs of the set are ignored, the result replaces all of them
+// leftover evs go into dump, as far as its 252 allows
+pub fn optimise_spread(
+    dex: &Dex,
+    pokemon: &Pokemon,
+    benchmarks: &[Benchmark],
+    dump: Option<Stat>
+) -> Result<SpreadResult, SpreadError> {
+    let mut set = pokemon.clone();
+    set.evs = Stats::default();
+    if stats::pokemon_stats(dex, &set).is_none() {
+        return Err(CalcError::UnknownSpecies { name: set.name.clone() }.into());
+    }
+
+    set.evs.spe = ev_steps(MAX_EV)
+        .find(|&ev| {
+            set.evs.spe = ev;
+            outspeeds(dex, &set, benchmarks)
+        })
+        .ok_or(SpreadError::Unreachable)?;
+
+    // 510 isn't a multiple of 4, the last 2 never count for anything
+    let budget = (MAX_EV_TOTAL - set.evs.spe as u16) / EV_STEP as u16 * EV_STEP as u16;
+    let [hp, def, spd] = cheapest_bulk(dex, &mut set, benchmarks, budget)?;
+    set.evs.hp = hp;
+    set.evs.def = def;
+    set.evs.spd = spd;
+
+    let spent: u16 = set.evs.to_array().iter().map(|&ev| ev as u16).sum();
+    let leftover = MAX_EV_TOTAL - spent;
+    if let Some(stat) = dump {
+        let room = MAX_EV - set.evs[stat];
+        let extra = (leftover.min(room as u16) as u8) / EV_STEP * EV_STEP;
+        set.evs[stat] += extra;
+    }
+
+    Ok(SpreadResult {
+        evs: set.evs,
+        stats: stats::pokemon_stats(dex, &set).ok_or(SpreadError::Unreachable)?,
+        leftover,
+    })
+}
+
+fn outspeeds(dex: &Dex, set: &Pokemon, benchmarks: &[Benchmark]) -> bool {
+    let item = SpeedItem::held(set);
+    let Some((_, own)) = speed::final_speed(dex, set, item, false) else {
+        return false;
+    };
+    benchmarks.iter().all(|benchmark| match benchmark {
+        Benchmark::Outspeed { speed } => own > *speed,
+        _ => true,
+    })
+}
+
+fn survives(dex: &Dex, set: &Pokemon, benchmarks: &[Benchmark]) -> Result<bool, CalcError> {
+    for benchmark in benchmarks {
+        if let Benchmark::Survive { attacker, move_name, field, hits } = benchmark {
+            let result = damage::calc_damage(dex, attacker, set, move_name, field)?;
+            if result.max() as u32 * *hits as u32 >= result.defender_hp as u32 {
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+
+// the hp, def and spd evs with the smallest total that survive everything
+fn cheapest_bulk(
+    dex: &Dex,
+    set: &mut Pokemon,
+    benchmarks: &[Benchmark],
+    budget: u16
+) -> Result<[u8; 3], SpreadError> {
+    let mut best: Option<(u16, [u8; 3])> = None;
+    let costs_more = |best: &Option<(u16, [u8; 3])>, spent: u16| {
+        spent > budget || best.is_some_and(|(total, _)| spent >= total)
+    };
+    for hp in ev_steps(MAX_EV) {
+        if costs_more(&best, hp as u16) {
+            break;
+        }
+        for def in ev_steps(MAX_EV) {
+            let spent = hp as u16 + def as u16;
+            if costs_more(&best, spent) {
+                break;
+            }
+            set.evs.hp = hp;
+            set.evs.def = def;
+            let spds: Vec<u8> = ev_steps((budget - spent).min(MAX_EV as u16) as u8).collect();
+            let Some(spd) = smallest_surviving(dex, set, benchmarks, &spds)? else {
+                continue;
+            };
+            let total = spent + spd as u16;
+            if !costs_more(&best, total) {
+                best = Some((total, [hp, def, spd]));
+            }
+        }
+    }
+    best.map(|(_, evs)| evs).ok_or(SpreadError::Unreachable)
+}
+
+// binary search, if the most spd we can give doesn't survive nothing will
+fn smallest_surviving(
+    dex: &Dex,
+    set: &mut Pokemon,
+    benchmarks: &[Benchmark],
+    spds: &[u8]
+) -> Result<Option<u8>, CalcError> {
+    let (mut low, mut high) = (0, spds.len());
+    set.evs.spd = spds[high - 1];
+    if !survives(dex, set, benchmarks)? {
+        return Ok(None);
+    }
+    while low < high - 1 {
+        let mid = (low + high - 1) / 2;
+        set.evs.spd = spds[mid];
+        if survives(dex, set, benchmarks)? {
+            high = mid + 1;
+        } else {
+            low = mid + 1;
+        }
+    }
+    Ok(Some(spds[low]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dex::Generation, moves, species};
+
+    fn test_dex() -> Dex {
+        let mut dex = Dex::build(Generation::Gen9).unwrap();
+        let names = &dex.maps.names;
+        let species = format!(
+            "{}\t95/115/90/80/90/60\tFire/Dark\tBlaze//Intimidate\t830\t{0}\n\
+             {}\t108/130/95/80/85/102\tDragon/Ground\tSand Veil//Rough Skin\t950\t{1}\n",
+            names["incineroar"], names["garchomp"]
+        );
+        dex.species = species::parse_species(&species, &dex.maps.abilities, true).unwrap();
+        let moves = &dex.maps.moves;
+        let move_data = format!(
+            "{}\tGround\tPhysical\t100\tallAdjacent\n{}\tWater\tSpecial\t90\tnormal\n",
+            moves["earthquake"], moves["surf"]
+        );
+        dex.move_data = moves::parse_moves(&move_data).unwrap();
+        dex
+    }
+
+    fn set(paste: &str) -> Pokemon {
+        parser::parse_pokepaste(paste.to_string()).unwrap().remove(0)
+    }
+
+    #[test]
+    fn test_speed_benchmarks() {
+        let dex = test_dex();
+        let incineroar = set("Incineroar\nLevel: 50\nCareful Nature");
+        let outspeed = |speed| vec![Benchmark::Outspeed { speed }];
+
+        // 80 with nothing, 86 takes 44
+        let spread = optimise_spread(&dex, &incineroar, &outspeed(85), None).unwrap();
+        assert_eq!(spread.evs.spe, 44);
+        assert_eq!(spread.stats.spe, 86);
+        assert_eq!(spread.leftover, 510 - 44);
+
+        let spread = optimise_spread(&dex, &incineroar, &outspeed(85), Some(Stat::Hp)).unwrap();
+        assert_eq!(spread.evs.to_array(), [252, 0, 0, 0, 0, 44]);
+
+        assert_eq!(
+            optimise_spread(&dex, &incineroar, &outspeed(200), None),
+            Err(SpreadError::Unreachable)
+        );
+    }
+
+    #[test]
+    fn test_bulk_benchmarks() {
+        let dex = test_dex();
+        let incineroar = set("Incineroar\nLevel: 50\nCareful Nature");
+        let garchomp = set("Garchomp\nLevel: 50\nEVs: 252 Atk\nAdamant Nature");
+        let benchmarks = [Benchmark::survive(garchomp.clone(), "Earthquake")];
+        let spread = optimise_spread(&dex, &incineroar, &benchmarks, Some(Stat::Atk)).unwrap();
+
+        // a physical hit, so nothing goes into spd
+        assert_eq!(spread.evs.spd, 0);
+        let mut candidate = incineroar.clone();
+        candidate.evs = spread.evs;
+        assert_eq!(survives(&dex, &candidate, &benchmarks), Ok(true));
+
+        // nothing cheaper survives, checked the slow way
+        let cheapest = ev_steps(MAX_EV)
+            .flat_map(|hp| ev_steps(MAX_EV).map(move |def| (hp, def)))
+            .filter(|&(hp, def)| {
+                candidate.evs = Stats::from_array([hp, 0, def, 0, 0, 0]);
+                survives(&dex, &candidate, &benchmarks).unwrap()
+            })
+            .map(|(hp, def)| hp as u16 + def as u16)
+            .min()
+            .unwrap();
+        assert_eq!(spread.evs.hp as u16 + spread.evs.def as u16, cheapest);
+        assert_eq!(spread.leftover, 510 - cheapest);
+        // everything left over, down to a multiple of 4
+        assert_eq!(spread.evs.atk as u16, spread.leftover.min(252) / 4 * 4);
+
+        // one more hit is too much for any spread
+        let benchmarks = [Benchmark::Survive {
+            attacker: Box::new(garchomp),
+            move_name: "Earthquake".to_string(),
+            field: Field::default(),
+            hits: 2,
+        }];
+        assert_eq!(
+            optimise_spread(&dex, &incineroar, &benchmarks, None),
+            Err(SpreadError::Unreachable)
+        );
+    }
+
+    // both kinds at once against the shipped data, checked the slow way
+    #[test]
+    #[ignore = "needs dex/species.txt and dex/movedata.txt, see dex_build"]
+    fn test_shipped_benchmarks() {
+        let dex = crate::get_dex();
+        let incineroar = set("Incineroar\nLevel: 50\nCareful Nature");
+        let garchomp = set("Garchomp\nLevel: 50\nEVs: 252 Atk\nAdamant Nature");
+        let benchmarks = [
+            Benchmark::Outspeed { speed: 85 },
+            Benchmark::survive(garchomp, "Earthquake"),
+        ];
+        let spread = optimise_spread(dex, &incineroar, &benchmarks, Some(Stat::Spd)).unwrap();
+        assert_eq!(spread.evs.spe, 44);
+        assert_eq!(spread.stats.spe, 86);
+
+        let mut candidate = incineroar.clone();
+        let cheapest = ev_steps(MAX_EV)
+            .flat_map(|hp| ev_steps(MAX_EV).map(move |def| (hp, def)))
+            .filter(|&(hp, def)| {
+                candidate.evs = Stats::from_array([hp, 0, def, 0, 0, 0]);
+                survives(dex, &candidate, &benchmarks[1..]).unwrap()
+            })
+            .map(|(hp, def)| hp as u16 + def as u16)
+            .min()
+            .unwrap();
+        assert_eq!(spread.evs.hp as u16 + spread.evs.def as u16, cheapest);
+        assert_eq!(spread.leftover, 510 - 44 - cheapest);
+    }
+
+    #[test]
+    fn test_benchmark_input() {
+        let input: BenchmarkInput = serde_json::from_str(
+            r#"{ "kind": "survive", "attacker": "Garchomp", "move": "Earthquake" }"#
+        ).unwrap();
+        let Benchmark::Survive { attacker, hits, field, .. } = input.try_into().unwrap() else {
+            panic!("not a survive benchmark");
+        };
+        assert_eq!(attacker.name, "garchomp");
+        assert_eq!(hits, 1);
+        assert_eq!(field, Field::default());
+
+        let input: BenchmarkInput = serde_json::from_str(
+            r#"{ "kind": "outspeed", "speed": 150 }"#
+        ).unwrap();
+        assert_eq!(Benchmark::try_from(input).unwrap(), Benchmark::Outspeed { speed: 150 });
+
+        let dex = test_dex();
+        let benchmarks = [Benchmark::survive(set("Pikachu"), "Surf")];
+        let incineroar = set("Incineroar");
+        assert!(matches!(
+            optimise_spread(&dex, &incineroar, &benchmarks, None),
+            Err(SpreadError::Calc(CalcError::UnknownSpecies { .. }))
+        ));
+    }
+}
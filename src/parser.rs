@@ -7,31 +7,110 @@
 use std::{
     //fmt::{self, Write},
     fmt,
+    mem,
+    ops::Range,
     sync::OnceLock,
 };
+use arrayvec::ArrayVec;
 use regex::Regex;
 use regex::Error as RegexError;
+use serde::{Deserialize, Serialize};
 
-use crate::error::ParseError;
+use crate::{
+    dex::{self, Generation},
+    diagnostic::{self, Diagnostic},
+    envelope::MAX_NICKNAME_CHARS,
+    error::{ParseError, ParseWarning},
+    json::PokemonSet,
+    model::{
+        self, Gender, MoveId, Nature, Stats, Type,
+        MAX_DYNAMAX_LEVEL, MAX_EV, MAX_HAPPINESS, MAX_IV, MAX_LEVEL, MAX_MOVES,
+        MIN_LEVEL, STAT_LABELS,
+    },
+    species::ABILITY_SLOTS,
+};
 
 // data struct logic ----------------------------------------------------------
 
 // our pokemon information struct 
 // makes it easier to convert to the intermediate binary format
 // easier to print out
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
+// as json it looks like showdown's PokemonSet, see json.rs
+// names are kept as written, the rest is checked while parsing (model.rs)
+// None is "not in the paste", showdown's default for that field
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "PokemonSet", try_from = "PokemonSet")]
 pub struct Pokemon {
+    pub nickname: String,
     pub name: String,
-    pub gender: String,
+    pub gender: Option<Gender>,
     pub item: String,
     pub ability: String,
-    pub level: String,
-    pub shiny: String,
-    pub tera: String,
-    pub evs: Tv,
-    pub nature: String,
-    pub ivs: Tv,
-    pub moves: Vec<String>,
+    pub level: Option<u8>,
+    pub shiny: bool,
+    pub happiness: Option<u8>,
+    // only one of these three makes it into the pack, depending on the gen
+    // gen 7
+    pub hidden_power: Option<Type>,
+    // gen 8
+    pub dynamax_level: Option<u8>,
+    pub gigantamax: bool,
+    // gen 9
+    pub tera: Option<Type>,
+    pub evs: Stats<u8>,
+    pub nature: Option<Nature>,
+    pub ivs: Stats<u8>,
+    pub moves: ArrayVec<MoveId, MAX_MOVES>,
+}
+
+// ivs start out perfect
+impl Default for Pokemon {
+    fn default() -> Self {
+        Pokemon {
+            nickname:       String::new(),
+            name:           String::new(),
+            gender:         None,
+            item:           String::new(),
+            ability:        String::new(),
+            level:          None,
+            shiny:          false,
+            happiness:      None,
+            hidden_power:   None,
+            dynamax_level:  None,
+            gigantamax:     false,
+            tera:           None,
+            evs:            Stats::splat(0),
+            nature:         None,
+            ivs:            Stats::splat(MAX_IV),
+            moves:          ArrayVec::new(),
+        }
+    }
+}
+
+// a team is the pokemon plus what showdown knows about it
+// format is the showdown format id, e.g. "gen9vgc2024regg"
+// folder is the teambuilder folder, empty if it isn't in one
+// the generation picks which dex the team is packed against
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Team {
+    pub generation: Generation,
+    pub format:     String,
+    pub folder:     String,
+    pub name:       String,
+    pub pokemon:    Vec<Pokemon>,
+}
+
+// just the paste, blank line between each pokemon
+impl fmt::Display for Team {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, pokemon) in self.pokemon.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", pokemon)?;
+        }
+        Ok(())
+    }
 }
 
 // not sure how to fix not displaying an info if it is empty,
@@ -39,145 +118,100 @@ pub struct Pokemon {
 // yeaa.. were gonna have to do it the wonky wayy..
 impl fmt::Display for Pokemon {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // NAME
-        write!(f, "{}", self.name)?;
+        // NICKNAME (NAME)
+        if !self.nickname.is_empty() {
+            write!(f, "{} ({})", self.nickname, self.name)?;
+        } else {
+            write!(f, "{}", self.name)?;
+        }
         // GENDER
-        if !self.gender.is_empty() {
-            write!(f, " ({})", self.gender.to_uppercase())?;
+        if let Some(gender) = self.gender {
+            write!(f, " ({})", gender.letter())?;
         }
         // ITEM
         if !self.item.is_empty() {
-            write!(f, " @ {}\n", self.item)?;
+            writeln!(f, " @ {}", self.item)?;
         } else {
-            write!(f, "\n")?;
+            writeln!(f)?;
         }
         // ABILITY
         if !self.ability.is_empty() {
             writeln!(f, "Ability: {}", self.ability)?;
         }
         // LEVEL
-        if !self.level.is_empty() {
-            writeln!(f, "Level: {}", self.level)?;
+        if let Some(level) = self.level {
+            writeln!(f, "Level: {}", level)?;
         }
         // SHINY
-        if self.shiny.to_lowercase() == "yes" {
+        if self.shiny {
             writeln!(f, "Shiny: Yes")?;
         }
+        // HAPPINESS
+        if let Some(happiness) = self.happiness {
+            writeln!(f, "Happiness: {}", happiness)?;
+        }
+        // HIDDEN POWER
+        if let Some(hidden_power) = self.hidden_power {
+            writeln!(f, "Hidden Power: {}", hidden_power)?;
+        }
+        // DYNAMAX
+        if let Some(dynamax_level) = self.dynamax_level {
+            writeln!(f, "Dynamax Level: {}", dynamax_level)?;
+        }
+        if self.gigantamax {
+            writeln!(f, "Gigantamax: Yes")?;
+        }
         // TERA
-        if !self.tera.is_empty() {
-            writeln!(f, "Tera Type: {}", self.tera)?;
+        if let Some(tera) = self.tera {
+            writeln!(f, "Tera Type: {}", tera)?;
         }
         // EVS
-        write!(f, "{}", self.evs)?;
+        printtvs(f, "EVs", &self.evs, 0)?;
         // NATURE
-        if !self.nature.is_empty() {
-            writeln!(f, "{} Nature", self.nature)?;
+        if let Some(nature) = self.nature {
+            writeln!(f, "{} Nature", nature)?;
         }
         // IVS
-        write!(f, "{}", self.ivs)?;
+        printtvs(f, "IVs", &self.ivs, MAX_IV)?;
         // MOVES
-        if !self.moves.iter().all(|m| m.is_empty()) {
-            for m in &self.moves {
-                if !m.is_empty() {
-                    write!(f, "- {m}\n")?;
-                }
-            }
+        for m in &self.moves {
+            writeln!(f, "- {m}")?;
         }
         Ok(())
     }
 }
 
-// training values
-// needs ifiv
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
-pub struct Tv {
-    pub ifiv:   bool,
-    pub hp:     String,
-    pub atk:    String,
-    pub def:    String,
-    pub spa:    String,
-    pub spd:    String,
-    pub spe:    String,
-}
-
-impl fmt::Display for Tv {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // we don't want to display IVs that are 31 and EVs that are 0
-        if self.ifiv {
-            printtvs(f, self, "31")?
-        } else {
-            printtvs(f, self, "0")?
-        }
-        Ok(())
-    }
-}
-
-
-// yeah idk this is just the way im gonna try it
-//fn printtvs(ivs: &Tv, cmp: &str) -> String {
-fn printtvs(f: &mut fmt::Formatter, ivs: &Tv, cmp: &str) -> fmt::Result {
-    //let mut text = String::new();
-    let mut v: Vec<String> = Vec::new();
-
-    if ivs.hp   != cmp { v.push(format!("{} HP", ivs.hp)); }
-    if ivs.atk  != cmp { v.push(format!("{} Atk", ivs.atk)); }
-    if ivs.def  != cmp { v.push(format!("{} Def", ivs.def)); }
-    if ivs.spa  != cmp { v.push(format!("{} SpA", ivs.spa)); }
-    if ivs.spd  != cmp { v.push(format!("{} SpD", ivs.spd)); }
-    if ivs.spe  != cmp { v.push(format!("{} Spe", ivs.spe)); }
+// we don't want to display IVs that are 31 and EVs that are 0
+fn printtvs(
+    f: &mut fmt::Formatter,
+    label: &str,
+    stats: &Stats<u8>,
+    default: u8
+) -> fmt::Result {
+    let v: Vec<String> = stats
+        .to_array()
+        .iter()
+        .zip(STAT_LABELS)
+        .filter(|(value, _)| **value != default)
+        .map(|(value, stat)| format!("{} {}", value, stat))
+        .collect();
 
     if v.is_empty() {
         return Ok(());
     }
 
-    let label = match cmp {
-        "31" => "IVs",
-        "0" => "EVs",
-        _ => "error",
-    };
-
     write!(f, "{}: {}", label, v.join(" / "))?;
     writeln!(f)?;
 
     Ok(())
- 
-    /*
-    for (i, j) in v.iter().enumerate() {
-        write!(&mut text, "{}", j).unwrap();
-        if i != v.len() - 1 && v.len() != 1 {
-            write!(&mut text, " / ").unwrap();
-        }
-    }
-    //println!("{}", &text);
-    if text.is_empty() {
-        text
-    } else {
-        let value = match cmp {
-            "31" => "IVs",
-            "0" => "EVs",
-            _ => "error",
-        };
-        let mut full = String::new();
-        writeln!(&mut full, "{}: {}", value, text).unwrap();
-        full
-    }
-    */
 }
 
 // parsing logic --------------------------------------------------------------
 
 // this is the main function being called from this module
+// error spans point into paste, see diagnostic.rs
 pub fn parse_pokepaste(paste: String) -> Result<Vec<Pokemon>, ParseError>{
-    //let text = paste.trim().to_lowercase();
-    let text = paste.trim();
-    if text.is_empty() {
-        return Err(ParseError::EmptyInput);
-    }
-
-    split_into_blocks(text)
-        .into_iter()
-        .map(parse_pokemon)
-        .collect()
+    parse_blocks(&paste, 0..paste.len())
 
     /*
     //println!("{}", text);
@@ -194,24 +228,465 @@ pub fn parse_pokepaste(paste: String) -> Result<Vec<Pokemon>, ParseError>{
     */
 }
 
-// by convention there are two new lines between each pokemon block
-// but do we wanna make this more robust?
-// just gotta watch out for those carriage returns cause of windows
-fn split_into_blocks(text: &str) -> Vec<String> {
-    if text.contains("\r") {
-        text.split("\r\n\r\n")
-        //text.split("\r\n")
-            .map(|t| t.into())
-            .collect()
+// everything wrong with a paste at once, instead of only the first thing
+// pokemon is whatever could be parsed, a block is only left out when it
+// has no name to go on, a bad line is skipped and the rest of the set kept
+#[derive(Debug, Default)]
+pub struct ParseReport {
+    pub pokemon:    Vec<Pokemon>,
+    pub errors:     Vec<ParseError>,
+    pub warnings:   Vec<ParseWarning>,
+}
 
-    } else { 
-        text.split("\n\n")
-        //text.split("\n")
-            .map(|t| t.into())
-            .collect()
+impl ParseReport {
+    // errors and warnings together, in the order they show up in the paste
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics: Vec<Diagnostic> = self
+            .errors
+            .iter()
+            .map(Diagnostic::from)
+            .chain(self.warnings.iter().map(Diagnostic::from))
+            .collect();
+        diagnostics.sort_by_key(|d| d.span.map(|span| span.start));
+        diagnostics
     }
 }
 
+// parse_pokepaste that doesn't stop, spans point into paste the same way
+pub fn check_pokepaste(paste: &str) -> ParseReport {
+    check_blocks(paste, 0..paste.len())
+}
+
+// the pokemon in input[range], spans are still relative to all of input
+// the first error is the one the whole parse fails with
+fn parse_blocks(input: &str, range: Range<usize>) -> Result<Vec<Pokemon>, ParseError> {
+    let mut report = check_blocks(input, range);
+    if report.errors.is_empty() {
+        Ok(report.pokemon)
+    } else {
+        Err(report.errors.swap_remove(0))
+    }
+}
+
+fn check_blocks(input: &str, range: Range<usize>) -> ParseReport {
+    let mut report = ParseReport::default();
+    //let text = paste.trim().to_lowercase();
+    if input[range.clone()].trim().is_empty() {
+        report.errors.push(ParseError::EmptyInput);
+        return report;
+    }
+
+    for block in split_into_blocks(input, range) {
+        match parse_pokemon(input, &block, &mut report) {
+            Ok(pokemon) => report.pokemon.push(pokemon),
+            Err(err) => report.errors.push(err),
+        }
+    }
+    report
+}
+
+// showdown's "Backup all teams" export, every team starts with
+// === [gen9ou] Folder/Team Name ===
+// format and folder are both optional
+// anything before the first header is kept as a team without a name
+pub fn parse_backup(backup: String) -> Result<Vec<Team>, ParseError> {
+    let mut teams = Vec::new();
+    // None until we see the first header
+    let mut current: Option<Team> = None;
+    // the body is kept as a byte range so error spans match the backup
+    let mut body_start = 0;
+    let mut offset = 0;
+
+    for line in backup.split_inclusive('\n') {
+        if let Some(header) = backup_header(line.trim()) {
+            finish_backup_team(&mut teams, current, &backup, body_start..offset)?;
+            current = Some(parse_backup_header(header));
+            body_start = offset + line.len();
+        }
+        offset += line.len();
+    }
+    finish_backup_team(&mut teams, current, &backup, body_start..offset)?;
+
+    if teams.is_empty() {
+        return Err(ParseError::EmptyInput);
+    }
+    Ok(teams)
+}
+
+// the reverse, same layout showdown exports
+// a blank line after every pokemon and after every team
+pub fn write_backup(teams: &[Team]) -> String {
+    let mut text = String::new();
+    for team in teams {
+        text.push_str("=== ");
+        if !team.format.is_empty() {
+            text.push_str(&format!("[{}] ", team.format));
+        }
+        if !team.folder.is_empty() {
+            text.push_str(&format!("{}/", team.folder));
+        }
+        text.push_str(&format!("{} ===\n\n", team.name));
+        for pokemon in &team.pokemon {
+            text.push_str(&format!("{}\n", pokemon));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+// "=== inside ===" -> "inside"
+fn backup_header(line: &str) -> Option<&str> {
+    line
+        .strip_prefix("===")?
+        .strip_suffix("===")
+        .map(|inside| inside.trim())
+}
+
+// same splitting rules as the teambuilder's import,
+// the format is in brackets and the folder is up to the last slash
+fn parse_backup_header(header: &str) -> Team {
+    let mut team = Team::default();
+    let mut rest = header;
+    if let Some(bracketed) = rest.strip_prefix('[') {
+        if let Some((format, after)) = bracketed.split_once(']') {
+            team.format = format.trim().to_string();
+            rest = after.trim();
+        }
+    }
+    (team.folder, team.name) = split_team_path(rest);
+    // parsing doesn't need the dex, so a gen we can't pack stays the default,
+    // the format still says what it really was
+    team.generation = Generation::from_format(&team.format)
+        .and_then(Result::ok)
+        .unwrap_or_default();
+    team
+}
+
+// showdown's own packed team string, the one the teambuilder keeps in
+// localStorage, sets are separated by ']' and fields by '|'
+// NICKNAME|SPECIES|ITEM|ABILITY|MOVES|NATURE|EVS|GENDER|IVS|SHINY|LEVEL|MISC
+// MISC is HAPPINESS,HIDDENPOWERTYPE,POKEBALL,GIGANTAMAX,DYNAMAXLEVEL,TERATYPE
+// (showdown's docs swap pokeball and hidden power, its code doesn't)
+// pokeball has nowhere to go in Pokemon so it is dropped
+//
+// the client writes an ability that's one of the species' own as its slot,
+// "" for slot 0, "1" or "H", anything else as its id
+// "" is kept as "0" so it isn't mistaken for no ability, the dex turns
+// slots into names once it knows the species (Dex::resolve_ability)
+//
+// the packed string is one line, so spans are the set's place in the team
+// and the bytes of the field that was wrong
+pub fn parse_packed_team(packed: &str) -> Result<Vec<Pokemon>, ParseError> {
+    if packed.trim().is_empty() {
+        return Err(ParseError::EmptyInput);
+    }
+    let mut offset = packed.len() - packed.trim_start().len();
+    packed
+        .trim()
+        .split(']')
+        .enumerate()
+        .map(|(i, set)| {
+            let start = offset;
+            offset += set.len() + 1;
+            parse_packed_set(set)
+                .map_err(|err| diagnostic::locate(err, packed, i, start, set))
+        })
+        .collect()
+}
+
+const PACKED_FIELDS: usize = 12;
+
+fn parse_packed_set(set: &str) -> Result<Pokemon, ParseError> {
+    let fields: Vec<&str> = set.split('|').collect();
+    if fields.len() != PACKED_FIELDS {
+        return Err(ParseError::MalformedLine {
+            line: set.to_string(),
+            span: None,
+        });
+    }
+    let mut pokemon = Pokemon::default();
+
+    // the species is only written when there's a nickname
+    if fields[1].is_empty() {
+        pokemon.name = fields[0].to_string();
+    } else {
+        pokemon.nickname = fields[0].to_string();
+        pokemon.name = fields[1].to_string();
+    }
+    if pokemon.name.is_empty() {
+        return Err(ParseError::MissingName {
+            block: set.to_string(),
+            span: None,
+        });
+    }
+    if pokemon.nickname.chars().count() > MAX_NICKNAME_CHARS {
+        return Err(ParseError::NicknameTooLong {
+            nickname: pokemon.nickname,
+            span: None,
+        });
+    }
+
+    pokemon.item = fields[2].to_string();
+    pokemon.ability = match fields[3] {
+        "" => ABILITY_SLOTS[0].to_string(),
+        ability => ability.to_string(),
+    };
+    for m in fields[4].split(',').filter(|m| !m.is_empty()) {
+        // a packed team has nowhere to show warnings
+        push_move(&mut pokemon, m, set, &mut Vec::new())?;
+    }
+    pokemon.nature = model::parse_optional(fields[5])?;
+    pokemon.evs = parse_packed_tvs(fields[6], "EVs", 0, MAX_EV)?;
+    pokemon.gender = Gender::parse(fields[7])?;
+    pokemon.ivs = parse_packed_tvs(fields[8], "IVs", MAX_IV, MAX_IV)?;
+    pokemon.shiny = fields[9] == "S";
+    pokemon.level = parse_level(fields[10])?;
+
+    let misc: Vec<&str> = fields[11].split(',').collect();
+    let misc_field = |i: usize| misc.get(i).copied().unwrap_or_default();
+    pokemon.happiness = parse_happiness(misc_field(0))?;
+    pokemon.hidden_power = model::parse_optional(misc_field(1))?;
+    pokemon.gigantamax = misc_field(3) == "G";
+    pokemon.dynamax_level = parse_dynamax_level(misc_field(4))?;
+    pokemon.tera = model::parse_optional(misc_field(5))?;
+
+    Ok(pokemon)
+}
+
+// "hp,atk,def,spa,spd,spe", an empty value (or field) is the default
+fn parse_packed_tvs(
+    text: &str,
+    field: &'static str,
+    default: u8,
+    max: u8
+) -> Result<Stats<u8>, ParseError> {
+    let mut stats = Stats::splat(default);
+    if text.is_empty() {
+        return Ok(stats);
+    }
+    let values: Vec<&str> = text.split(',').collect();
+    if values.len() != 6 {
+        return Err(ParseError::MalformedTvString {
+            line: text.to_string(),
+            span: None,
+        });
+    }
+    for (label, value) in STAT_LABELS.iter().zip(values) {
+        if !value.is_empty() {
+            if let Some(stat) = stats.get_mut(label) {
+                *stat = model::parse_number(field, value, 0, max)?;
+            }
+        }
+    }
+    Ok(stats)
+}
+
+// the reverse, names become showdown ids like the teambuilder writes them
+pub fn write_packed_team(team: &[Pokemon]) -> String {
+    team
+        .iter()
+        .map(write_packed_set)
+        .collect::<Vec<String>>()
+        .join("]")
+}
+
+fn write_packed_set(pokemon: &Pokemon) -> String {
+    let (name, species) = if pokemon.nickname.is_empty() {
+        (pokemon.name.clone(), String::new())
+    } else {
+        (pokemon.nickname.clone(), dex::to_id(&pokemon.name))
+    };
+    let moves: Vec<String> = pokemon
+        .moves
+        .iter()
+        .map(|m| m.id().to_string())
+        .collect();
+    // 100, 10 and 255 are what an empty field means anyway
+    let level = match pokemon.level {
+        Some(level) if level != MAX_LEVEL => level.to_string(),
+        _ => String::new(),
+    };
+    let dynamax_level = match pokemon.dynamax_level {
+        Some(level) if level != MAX_DYNAMAX_LEVEL => level.to_string(),
+        _ => String::new(),
+    };
+    let happiness = match pokemon.happiness {
+        Some(happiness) if happiness != MAX_HAPPINESS => happiness.to_string(),
+        _ => String::new(),
+    };
+    let type_name = |t: Option<Type>| t.map(Type::name).unwrap_or_default();
+
+    let mut fields = vec![
+        name,
+        species,
+        dex::to_id(&pokemon.item),
+        packed_ability(&pokemon.ability),
+        moves.join(","),
+        pokemon.nature.map(Nature::name).unwrap_or_default().to_string(),
+        write_packed_tvs(&pokemon.evs, 0),
+        pokemon.gender.map(Gender::letter).unwrap_or_default().to_string(),
+        write_packed_tvs(&pokemon.ivs, MAX_IV),
+        if pokemon.shiny { "S" } else { "" }.to_string(),
+        level,
+    ];
+
+    // the misc field is left out entirely when there's nothing in it
+    let misc = [
+        happiness.as_str(),
+        type_name(pokemon.hidden_power),
+        "",
+        if pokemon.gigantamax { "G" } else { "" },
+        &dynamax_level,
+        type_name(pokemon.tera),
+    ];
+    if misc.iter().all(|m| m.is_empty()) {
+        fields.push(String::new());
+    } else {
+        fields.push(misc.join(","));
+    }
+
+    fields.join("|")
+}
+
+// slots go back the way the client wrote them, "H" isn't an id
+fn packed_ability(ability: &str) -> String {
+    match ABILITY_SLOTS.iter().position(|slot| *slot == ability) {
+        Some(0) => String::new(),
+        Some(_) => ability.to_string(),
+        None => dex::to_id(ability),
+    }
+}
+
+fn write_packed_tvs(stats: &Stats<u8>, default: u8) -> String {
+    let values = stats
+        .to_array()
+        .map(|v| if v == default { String::new() } else { v.to_string() });
+    if values.iter().all(|v| v.is_empty()) {
+        String::new()
+    } else {
+        values.join(",")
+    }
+}
+
+// the checks every format shares, see model.rs for the limits
+fn parse_level(text: &str) -> Result<Option<u8>, ParseError> {
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    model::parse_number("Level", text, MIN_LEVEL, MAX_LEVEL).map(Some)
+}
+
+fn parse_dynamax_level(text: &str) -> Result<Option<u8>, ParseError> {
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    model::parse_number("Dynamax Level", text, 0, MAX_DYNAMAX_LEVEL).map(Some)
+}
+
+fn parse_happiness(text: &str) -> Result<Option<u8>, ParseError> {
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    model::parse_number("Happiness", text, 0, MAX_HAPPINESS).map(Some)
+}
+
+// a fifth move is an error, not something to drop quietly
+// a repeat is only a warning, showdown's validator is the one to reject it
+fn push_move(
+    pokemon: &mut Pokemon,
+    m: &str,
+    block: &str,
+    warnings: &mut Vec<ParseWarning>
+) -> Result<(), ParseError> {
+    let m: MoveId = m.parse()?;
+    if pokemon.moves.contains(&m) {
+        warnings.push(ParseWarning::DuplicateMove {
+            name: m.to_string(),
+            span: None,
+        });
+    }
+    pokemon
+        .moves
+        .try_push(m)
+        .map_err(|_| ParseError::TooManyMoves {
+            block: block.to_string(),
+            span: None,
+        })
+}
+
+// "Folder/Sub/Name" -> ("Folder/Sub", "Name")
+// the folder is everything up to the last slash, like the teambuilder
+pub fn split_team_path(path: &str) -> (String, String) {
+    match path.rfind('/') {
+        Some(slash) if slash > 0 => {
+            (path[..slash].to_string(), path[slash + 1..].to_string())
+        },
+        _ => (String::new(), path.to_string()),
+    }
+}
+
+// a header with nothing under it is still a team, just an empty one
+// text before the first header only counts if there is something there
+fn finish_backup_team(
+    teams: &mut Vec<Team>,
+    team: Option<Team>,
+    backup: &str,
+    body: Range<usize>
+) -> Result<(), ParseError> {
+    let empty = backup[body.clone()].trim().is_empty();
+    let mut team = match team {
+        Some(team) => team,
+        None if empty => return Ok(()),
+        None => Team::default(),
+    };
+    if !empty {
+        team.pokemon = parse_blocks(backup, body)?;
+    }
+    teams.push(team);
+    Ok(())
+}
+
+// one pokemon block, its lines are trimmed and never empty
+// each line keeps the byte offset it starts at in the original input
+struct Block<'a> {
+    index: usize,
+    lines: Vec<(usize, &'a str)>,
+}
+
+impl Block<'_> {
+    // for errors that quote the whole block
+    fn text(&self) -> String {
+        self.lines
+            .iter()
+            .map(|(_, line)| *line)
+            .collect::<Vec<&str>>()
+            .join("\n")
+    }
+}
+
+// a blank line, or a run of them, ends a block like in showdown's importer
+// lines that are only spaces count as blank, trim takes care of \r\n too
+fn split_into_blocks(input: &str, range: Range<usize>) -> Vec<Block<'_>> {
+    let mut blocks = Vec::new();
+    let mut lines = Vec::new();
+    let mut offset = range.start;
+
+    for raw in input[range].split_inclusive('\n') {
+        let line = raw.trim();
+        if !line.is_empty() {
+            let indent = raw.len() - raw.trim_start().len();
+            lines.push((offset + indent, line));
+        } else if !lines.is_empty() {
+            blocks.push(Block { index: blocks.len(), lines: mem::take(&mut lines) });
+        }
+        offset += raw.len();
+    }
+    if !lines.is_empty() {
+        blocks.push(Block { index: blocks.len(), lines });
+    }
+    blocks
+}
+
 static GENDER_REGEX: OnceLock<Result<Regex, RegexError>> = OnceLock::new();
 static NICKNAME_REGEX: OnceLock<Result<Regex, RegexError>> = OnceLock::new();
 
@@ -220,7 +695,7 @@ static NICKNAME_REGEX: OnceLock<Result<Regex, RegexError>> = OnceLock::new();
 // map_err to operate on Result and wrap with ParseError
 fn get_gender_regex() -> Result<&'static Regex, ParseError> {
     GENDER_REGEX
-        .get_or_init(|| { Regex::new(r"\(([mf])\)") })
+        .get_or_init(|| { Regex::new(r"\(([MFmf])\)") })
         .as_ref()
         .map_err(|err| ParseError::Regex(err.clone()))
 }
@@ -233,33 +708,41 @@ fn get_nickname_regex() -> Result<&'static Regex, ParseError> {
 }
 
 // this will parse one pokemon at a time
-fn parse_pokemon(text: String) -> Result<Pokemon, ParseError> {
+// input is the whole paste, only used to work out error spans
+// problems the set survives go in report, Err is for a block with no pokemon
+fn parse_pokemon(
+    input: &str,
+    block: &Block,
+    report: &mut ParseReport
+) -> Result<Pokemon, ParseError> {
     let gender_regex = get_gender_regex()?;
     let nickname_regex = get_nickname_regex()?;
     let mut pokemon = Pokemon::default();
 
-    // we only want to consider non empty lines from a block
-    let lines: Vec<&str> = 
-        text
-            .lines()
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty())
-            .collect();
-
-    if lines.is_empty() {
-        return Err(ParseError::EmptyBlock);
-    }
+    // an error on a line points at that line, or the part of it it quotes
+    let locate = |err: ParseError, (offset, line): (usize, &str)| {
+        diagnostic::locate(err, input, block.index, offset, line)
+    };
+    let missing_name = || ParseError::MissingName {
+        block: block.text(),
+        span: None,
+    };
 
     // first line of a block needs to be a pokemon name
     // this is in line with PS behavior
-    let header = lines[0];
+    let Some(&header_line) = block.lines.first() else {
+        return Err(ParseError::EmptyBlock);
+    };
+    let header = header_line.1;
     if header.contains(':') || header.starts_with('-') {
-        return Err(ParseError::MissingName { block: text });
+        return Err(locate(missing_name(), header_line));
     }
 
     // we set default name as full field
-    // this handles NAME (GENDER) @ ITEM fully
-    let mut name: String = header.to_lowercase().to_string();
+    // this handles NICKNAME (NAME) (GENDER) @ ITEM fully
+    // keep the original case until the end, nicknames need it
+    let mut name: String = header.to_string();
+    let mut nickname = String::new();
     let mut gender = String::new();
     let mut item = String::new();
     
@@ -272,12 +755,12 @@ fn parse_pokemon(text: String) -> Result<Pokemon, ParseError> {
         item = item_part;
     }
     
-    // check for (f) or (m) in the name
+    // check for (F) or (M) in the name
     if let Some(captures) = gender_regex.captures(&name) {
         if let Some(gender_match) = captures.get(1) {
             gender = gender_match
                         .as_str()
-                        .to_string();
+                        .to_lowercase();
             name = gender_regex
                     .replace_all(&name, "")
                     .trim()
@@ -286,9 +769,14 @@ fn parse_pokemon(text: String) -> Result<Pokemon, ParseError> {
     }
 
     // check if there is a nickname 
+    // whatever is in front of the parentheses is the nickname
     if name.contains("(") && name.contains(")") {
         if let Some(captures) = nickname_regex.captures(&name) {
-            if let Some(name_match) = captures.get(1) {
+            if let (Some(whole), Some(name_match)) = 
+                (captures.get(0), captures.get(1)) {
+                nickname = name[..whole.start()]
+                        .trim()
+                        .to_string();
                 // extract species name from parentheses
                 name = name_match
                         .as_str()
@@ -297,58 +785,136 @@ fn parse_pokemon(text: String) -> Result<Pokemon, ParseError> {
             }
         }
     }
+
+    // the pokemon is still fine without it
+    if nickname.chars().count() > MAX_NICKNAME_CHARS {
+        let err = ParseError::NicknameTooLong { nickname, span: None };
+        report.errors.push(locate(err, header_line));
+        nickname = String::new();
+    }
     
     // assign header info to pokemon struct
+    pokemon.nickname = nickname;
     pokemon.name = name.to_lowercase();
     pokemon.item = item.to_lowercase();
-    pokemon.gender = gender;
+    match Gender::parse(&gender) {
+        Ok(gender) => pokemon.gender = gender,
+        Err(err) => report.errors.push(locate(err, header_line)),
+    }
 
     // now we can parse over the rest of the block
-    for line in &lines[1..] {
-        //println!("{}", line);
-        // split line via : into pairs
-        let parts: Vec<&str> = line.split(": ").collect();
-        // converting to lowercase makes the parsing easier
-        let lower = parts[0].to_lowercase();
-        //println!("{:?}", parts);
-        if parts.len() >= 2 {
-            //println!(" 2");
-            let value = parts[1].trim().to_string();
-            match lower.as_str() {
-                "ability"   => pokemon.ability = value,
-                "level"     => pokemon.level = value,
-                "tera type" => pokemon.tera = value,
-                "shiny"     => pokemon.shiny = value,
-                "evs"       => pokemon.evs = parse_tvs(value, false)?,
-                "ivs"       => pokemon.ivs = parse_tvs(value, true)?,
-                // should just ignore anything not defined
-                _ => {},
-            }
-        } else if lower.contains("nature") {
-            let nature: Vec<&str> = lower.split(" nature").collect();
-            pokemon.nature = nature[0].trim().into();
-        } else if parts[0].starts_with("-") {
-            if parts[0].len() > 1 {
-                pokemon.moves.push(parts[0][1..].trim().into());
-            } else {
-                return Err(ParseError::MalformedLine {
-                    line: line.to_string()
-                });
-            }
-
-        } else {
-
+    // a line that fails leaves the pokemon as it was before it
+    let text = block.text();
+    for &line in &block.lines[1..] {
+        let seen = report.warnings.len();
+        if let Err(err) = parse_line(&mut pokemon, line.1, &text, &mut report.warnings) {
+            report.errors.push(locate(err, line));
         }
+        let located: Vec<ParseWarning> = report
+            .warnings
+            .drain(seen..)
+            .map(|w| diagnostic::locate(w, input, block.index, line.0, line.1))
+            .collect();
+        report.warnings.extend(located);
     }
     //println!("\n\n\n{}", pokemon);
     if pokemon.name.is_empty() {
-        return Err(ParseError::MissingName { block: text });
+        return Err(locate(missing_name(), header_line));
     }
     Ok(pokemon)
 }
 
-fn parse_tvs(text: String, ifiv: bool) -> Result<Tv, ParseError> {
-    let mut tv = Tv::default();
+// everything after the header, text is the block for errors that quote it
+// warnings don't change what gets parsed, they only say what was odd
+fn parse_line(
+    pokemon: &mut Pokemon,
+    line: &str,
+    text: &str,
+    warnings: &mut Vec<ParseWarning>
+) -> Result<(), ParseError> {
+    let unknown = || ParseWarning::UnknownLine {
+        line: line.to_string(),
+        span: None,
+    };
+    let default = || ParseWarning::DefaultValue {
+        line: line.to_string(),
+        span: None,
+    };
+    //println!("{}", line);
+    // split line via : into pairs
+    let parts: Vec<&str> = line.split(": ").collect();
+    // converting to lowercase makes the parsing easier
+    let lower = parts[0].to_lowercase();
+    //println!("{:?}", parts);
+    if parts.len() >= 2 {
+        //println!(" 2");
+        let value = parts[1].trim();
+        match lower.as_str() {
+            "ability"   => pokemon.ability = value.to_string(),
+            "level"     => pokemon.level = parse_level(value)?,
+            "tera type" => pokemon.tera = model::parse_optional(value)?,
+            "shiny"     => {
+                pokemon.shiny = model::parse_yes_no("Shiny", value)?;
+                if !pokemon.shiny {
+                    warnings.push(default());
+                }
+            },
+            "hidden power"  => {
+                pokemon.hidden_power = model::parse_optional(value)?
+            },
+            "dynamax level" => {
+                pokemon.dynamax_level = parse_dynamax_level(value)?
+            },
+            "happiness"     => pokemon.happiness = parse_happiness(value)?,
+            "gigantamax"    => {
+                pokemon.gigantamax = model::parse_yes_no("Gigantamax", value)?;
+                if !pokemon.gigantamax {
+                    warnings.push(default());
+                }
+            },
+            "evs"       => {
+                pokemon.evs = parse_tvs(value, "EVs", 0, MAX_EV, warnings)?
+            },
+            "ivs"       => {
+                pokemon.ivs = parse_tvs(value, "IVs", MAX_IV, MAX_IV, warnings)?
+            },
+            // showdown knows this but the record has no room for it
+            "pokeball"  => {
+                warnings.push(ParseWarning::DroppedField {
+                    field: parts[0].trim().to_string(),
+                    span: None,
+                })
+            },
+            // should just ignore anything not defined
+            _ => warnings.push(unknown()),
+        }
+    } else if lower.contains("nature") {
+        let nature: Vec<&str> = lower.split(" nature").collect();
+        pokemon.nature = model::parse_optional(nature[0])?;
+    } else if parts[0].starts_with("-") {
+        if parts[0].len() > 1 {
+            push_move(pokemon, parts[0][1..].trim(), text, warnings)?;
+        } else {
+            return Err(ParseError::MalformedLine {
+                line: line.to_string(),
+                span: None,
+            });
+        }
+    } else {
+        warnings.push(unknown());
+    }
+    Ok(())
+}
+
+// stats that aren't mentioned keep the default, unknown stat names are skipped
+fn parse_tvs(
+    text: &str,
+    field: &'static str,
+    default: u8,
+    max: u8,
+    warnings: &mut Vec<ParseWarning>
+) -> Result<Stats<u8>, ParseError> {
+    let mut stats = Stats::splat(default);
     let parts: Vec<&str> = text.split(" / ").collect();
     for p in parts {
         //println!("p: -{}-", p);
@@ -356,21 +922,21 @@ fn parse_tvs(text: String, ifiv: bool) -> Result<Tv, ParseError> {
 
         // check for "VALUE STAT" format
         if c.len() != 2 {
-            return Err(ParseError::MalformedTvString { line: p.to_string() });
+            return Err(ParseError::MalformedTvString {
+                line: p.to_string(),
+                span: None,
+            });
         }
 
-        match c[1].to_lowercase().as_str() {
-            "hp" => {tv.hp = c[0].into()},
-            "atk" => {tv.atk = c[0].into()},
-            "def" => {tv.def = c[0].into()},
-            "spa" => {tv.spa = c[0].into()},
-            "spd" => {tv.spd = c[0].into()},
-            "spe" => {tv.spe = c[0].into()},
-            _ => {},
+        match stats.get_mut(c[1]) {
+            Some(stat) => *stat = model::parse_number(field, c[0], 0, max)?,
+            None => warnings.push(ParseWarning::UnknownStat {
+                stat: c[1].to_string(),
+                span: None,
+            }),
         }
     }
-    tv.ifiv = ifiv;
-    Ok(tv)
+    Ok(stats)
 }
 
     
@@ -378,6 +944,10 @@ fn parse_tvs(text: String, ifiv: bool) -> Result<Tv, ParseError> {
 mod tests {
     use super::*;
 
+    fn parse_one(paste: &str) -> Result<Pokemon, ParseError> {
+        parse_pokepaste(paste.to_string()).map(|mut team| team.remove(0))
+    }
+
     // make sure the parser extracts the strings correctly
     // include nickname and gender
     #[test]
@@ -387,6 +957,7 @@ Joe (Glimmora) (M) @ Focus Sash
 Ability: Toxic Debris
 Level: 50
 Shiny: Yes
+Happiness: 0
 Line that does nothing
 Tera Type: Grass
 EVs: 4 Def / 252 SpA / 252 Spe
@@ -395,36 +966,188 @@ IVs: 0 Atk
 - Mortal Spin
 - Power Gem
 "#;
-        let result = parse_pokemon(paste.to_string()).unwrap();
+        let result = parse_one(paste).unwrap();
 
+        assert_eq!(result.nickname, "Joe");
         assert_eq!(result.name, "glimmora");
-        assert_eq!(result.gender, "m");
+        assert_eq!(result.gender, Some(Gender::Male));
         assert_eq!(result.item, "focus sash");
         assert_eq!(result.ability, "Toxic Debris");
-        assert_eq!(result.level, "50");
-        assert_eq!(result.shiny, "Yes");
-        assert_eq!(result.tera, "Grass");
-        assert_eq!(result.evs.def, "4");
-        assert_eq!(result.evs.spa, "252");
-        assert_eq!(result.evs.spe, "252");
-        assert_eq!(result.nature, "timid");
-        assert_eq!(result.ivs.atk, "0");
-        assert_eq!(result.moves, vec!["Mortal Spin", "Power Gem"]);
+        assert_eq!(result.level, Some(50));
+        assert!(result.shiny);
+        assert_eq!(result.happiness, Some(0));
+        assert_eq!(result.tera, Some(Type::Grass));
+        assert_eq!(result.evs.def, 4);
+        assert_eq!(result.evs.spa, 252);
+        assert_eq!(result.evs.spe, 252);
+        assert_eq!(result.evs.hp, 0);
+        assert_eq!(result.nature, Some(Nature::Timid));
+        assert_eq!(result.ivs.atk, 0);
+        assert_eq!(result.ivs.hp, 31);
+        assert_eq!(result.moves.as_slice(), ["Mortal Spin", "Power Gem"]);
     }
     
+    #[test]
+    fn test_parse_older_gen_lines() {
+        let paste = "Charizard-Gmax\nHidden Power: Ice\n\
+                     Dynamax Level: 5\nGigantamax: Yes";
+        let result = parse_one(paste).unwrap();
+        assert_eq!(result.hidden_power, Some(Type::Ice));
+        assert_eq!(result.dynamax_level, Some(5));
+        assert!(result.gigantamax);
+    }
+
+    const BACKUP: &str = "\
+=== [gen9ou] Sand/Tyranitar Balance ===
+
+Tyranitar @ Leftovers
+Ability: Sand Stream
+- Stealth Rock
+
+Great Tusk
+- Rapid Spin
+
+
+=== [gen8ou] Bird Spam ===
+
+Corviknight
+Dynamax Level: 5
+- Roost
+
+
+=== No Format ===
+
+";
+
+    #[test]
+    fn test_parse_backup() {
+        let teams = parse_backup(BACKUP.to_string()).unwrap();
+        assert_eq!(teams.len(), 3);
+
+        assert_eq!(teams[0].format, "gen9ou");
+        assert_eq!(teams[0].folder, "Sand");
+        assert_eq!(teams[0].name, "Tyranitar Balance");
+        assert_eq!(teams[0].pokemon.len(), 2);
+        assert_eq!(teams[0].pokemon[1].name, "great tusk");
+
+        assert_eq!(teams[1].generation, Generation::Gen8);
+        assert_eq!(teams[1].folder, "");
+        assert_eq!(teams[1].pokemon[0].dynamax_level, Some(5));
+
+        assert_eq!(teams[2].format, "");
+        assert_eq!(teams[2].name, "No Format");
+        assert!(teams[2].pokemon.is_empty());
+    }
+
+    #[test]
+    fn test_write_backup_roundtrip() {
+        let teams = parse_backup(BACKUP.to_string()).unwrap();
+        let written = write_backup(&teams);
+        assert!(written.starts_with("=== [gen9ou] Sand/Tyranitar Balance ===\n\n"));
+        assert!(written.contains("\n\n=== No Format ===\n\n"));
+        assert_eq!(parse_backup(written).unwrap(), teams);
+    }
+
+    #[test]
+    fn test_backup_keeps_headerless_text() {
+        let teams = parse_backup("Pikachu\n\n=== Team ===\n\nEevee".into()).unwrap();
+        assert_eq!(teams.len(), 2);
+        assert_eq!(teams[0].name, "");
+        assert_eq!(teams[0].pokemon[0].name, "pikachu");
+        assert_eq!(teams[1].name, "Team");
+    }
+
+    const PACKED: &str = "\
+Gholdengo||choicespecs|goodasgold|makeitrain,shadowball,nastyplot,trick|\
+Timid|,,,252,4,252||,0,,,,|||,,,,,Steel]\
+Joe|glimmora|focussash|toxicdebris|mortalspin,powergem|Timid|\
+,,4,252,,252|M||S|50|0,,,,,]\
+Charizard||heavydutyboots|blaze|flamethrower|||||||,,,G,5,";
+
+    #[test]
+    fn test_parse_packed_team() {
+        let team = parse_packed_team(PACKED).unwrap();
+        assert_eq!(team.len(), 3);
+
+        assert_eq!(team[0].name, "Gholdengo");
+        assert_eq!(team[0].item, "choicespecs");
+        assert_eq!(team[0].moves.len(), 4);
+        assert_eq!(team[0].nature, Some(Nature::Timid));
+        assert_eq!(team[0].evs.spa, 252);
+        assert_eq!(team[0].ivs.atk, 0);
+        assert_eq!(team[0].ivs.hp, 31);
+        assert_eq!(team[0].tera, Some(Type::Steel));
+
+        assert_eq!(team[1].nickname, "Joe");
+        assert_eq!(team[1].name, "glimmora");
+        assert_eq!(team[1].gender, Some(Gender::Male));
+        assert!(team[1].shiny);
+        assert_eq!(team[1].level, Some(50));
+        assert_eq!(team[1].happiness, Some(0));
+
+        assert!(team[2].gigantamax);
+        assert_eq!(team[2].dynamax_level, Some(5));
+    }
+
+    #[test]
+    fn test_write_packed_team_roundtrip() {
+        let team = parse_packed_team(PACKED).unwrap();
+        assert_eq!(write_packed_team(&team), PACKED);
+    }
+
+    #[test]
+    fn test_packed_ability_slots() {
+        let packed = "Incineroar|||H|fakeout|||||||]Rillaboom||||fakeout|||||||";
+        let team = parse_packed_team(packed).unwrap();
+        assert_eq!(team[0].ability, "H");
+        assert_eq!(team[1].ability, "0");
+        assert_eq!(write_packed_team(&team), packed);
+    }
+
+    #[test]
+    fn test_malformed_packed_set() {
+        assert!(matches!(
+            parse_packed_team("Pikachu|||"),
+            Err(ParseError::MalformedLine { .. })
+        ));
+        assert!(matches!(
+            parse_packed_team("Pikachu||||||1,2|||||"),
+            Err(ParseError::MalformedTvString { .. })
+        ));
+        assert!(matches!(
+            parse_packed_team("Pikachu||||||,,,,,253|||||"),
+            Err(ParseError::OutOfRange { field: "EVs", .. })
+        ));
+        assert!(matches!(
+            parse_packed_team("Pikachu||||a,b,c,d,e|||||||"),
+            Err(ParseError::TooManyMoves { .. })
+        ));
+    }
+
     #[test]
     fn test_minimal_pokemon() {
         let paste = "Pikachu";
-        let result = parse_pokemon(paste.to_string()).unwrap();
+        let result = parse_one(paste).unwrap();
         assert_eq!(result.name, "pikachu");
+        assert!(result.nickname.is_empty());
         assert!(result.item.is_empty());
         assert!(result.ability.is_empty());
     }
     
+    #[test]
+    fn test_error_on_long_nickname() {
+        let paste = "Nineteen Characters (Pikachu)";
+        let result = parse_one(paste);
+        assert!(matches!(
+            result.unwrap_err(), 
+            ParseError::NicknameTooLong { .. }
+        ));
+    }
+
     #[test]
     fn test_error_on_missing_name() {
         let paste = "Ability: Intimidate";
-        let result = parse_pokemon(paste.to_string());
+        let result = parse_one(paste);
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), ParseError::MissingName { .. }));
     }
@@ -433,9 +1156,156 @@ IVs: 0 Atk
     #[test]
     fn test_gracefully_handles_malformed_ev_string() {
         let paste = "Snorlax\nEVs: 252 HP / Atk 252";
-        let result = parse_pokemon(paste.to_string()).unwrap();
-        assert_eq!(result.evs.hp, "252");
-        assert_eq!(result.evs.atk, "");
+        let result = parse_one(paste).unwrap();
+        assert_eq!(result.evs.hp, 252);
+        assert_eq!(result.evs.atk, 0);
+    }
+
+    fn span_text(input: &str, err: &ParseError) -> String {
+        let span = err.span().unwrap();
+        input[span.start..span.end].to_string()
+    }
+
+    #[test]
+    fn test_error_spans_point_into_the_input() {
+        let paste = "\n  Pikachu\n\n\n\nEevee @ Leftovers\nEVs: 4 HP / 300 Atk\n";
+        let err = parse_pokepaste(paste.to_string()).unwrap_err();
+        let span = err.span().unwrap();
+        assert_eq!(span.block, 1);
+        assert_eq!(span.line, 7);
+        assert_eq!(span.column, 13);
+        assert_eq!(span_text(paste, &err), "300");
+
+        let paste = "Pikachu\n- Thunderbolt\n-\n";
+        let err = parse_pokepaste(paste.to_string()).unwrap_err();
+        assert!(matches!(err, ParseError::MalformedLine { .. }));
+        assert_eq!(err.span().unwrap().line, 3);
+
+        let paste = "Pikachu\r\n\r\nNineteen Characters (Eevee)";
+        let err = parse_pokepaste(paste.to_string()).unwrap_err();
+        assert_eq!(span_text(paste, &err), "Nineteen Characters");
+    }
+
+    #[test]
+    fn test_blank_lines_with_spaces_split_blocks() {
+        let team = parse_pokepaste("Pikachu  \n   \nEevee".to_string()).unwrap();
+        assert_eq!(team.len(), 2);
+    }
+
+    #[test]
+    fn test_backup_and_packed_spans() {
+        let backup = "=== Team ===\n\nPikachu\nLevel: 0\n";
+        let err = parse_backup(backup.to_string()).unwrap_err();
+        assert_eq!(err.span().unwrap().line, 4);
+        assert_eq!(span_text(backup, &err), "0");
+
+        let packed = "Pikachu|||||||||||]Eevee|||||Sleepy||||||";
+        let err = parse_packed_team(packed).unwrap_err();
+        assert_eq!(err.span().unwrap().block, 1);
+        assert_eq!(span_text(packed, &err), "Sleepy");
+    }
+
+    // these used to turn into 0 on the way to the binary
+    #[test]
+    fn test_out_of_range_values_are_errors() {
+        let cases = [
+            ("Pikachu\nLevel: 300", "Level"),
+            ("Pikachu\nLevel: abc", "Level"),
+            ("Pikachu\nEVs: 300 Atk", "EVs"),
+            ("Pikachu\nIVs: 32 Spe", "IVs"),
+            ("Pikachu\nDynamax Level: 11", "Dynamax Level"),
+            ("Pikachu\nTera Type: Sound", "Type"),
+            ("Pikachu\nSleepy Nature", "Nature"),
+        ];
+        for (paste, field) in cases {
+            match parse_one(paste) {
+                Err(ParseError::OutOfRange { field: f, .. })
+                | Err(ParseError::InvalidValue { field: f, .. }) => {
+                    assert_eq!(f, field, "{}", paste);
+                },
+                other => panic!("{} parsed as {:?}", paste, other),
+            }
+        }
+        let five_moves = "Pikachu\n- A\n- B\n- C\n- D\n- E";
+        assert!(matches!(
+            parse_one(five_moves),
+            Err(ParseError::TooManyMoves { .. })
+        ));
+    }
+
+    #[test]
+    fn test_check_reports_every_error() {
+        let paste = "Pikachu\nLevel: 300\nEVs: 4 HP / 999 Spe\n- Thunderbolt\n\n\
+                     Ability: Static\n\n\
+                     Eevee\nTera Type: Sound\n- Bite\n";
+        let report = check_pokepaste(paste);
+        assert_eq!(report.errors.len(), 4);
+        assert!(matches!(report.errors[2], ParseError::MissingName { .. }));
+        let texts: Vec<String> = report
+            .errors
+            .iter()
+            .map(|err| span_text(paste, err))
+            .collect();
+        assert_eq!(texts[0], "300");
+        assert_eq!(texts[1], "999");
+        assert_eq!(texts[3], "Sound");
+
+        // the bad lines are skipped, everything else is kept
+        assert_eq!(report.pokemon.len(), 2);
+        assert_eq!(report.pokemon[0].level, None);
+        assert_eq!(report.pokemon[0].moves.as_slice(), ["Thunderbolt"]);
+        assert_eq!(report.pokemon[1].name, "eevee");
+        assert_eq!(report.pokemon[1].moves.as_slice(), ["Bite"]);
+
+        // the strict parse fails with the first of them
+        let err = parse_pokepaste(paste.to_string()).unwrap_err();
+        assert_eq!(err.to_string(), report.errors[0].to_string());
+        assert_eq!(err.span(), report.errors[0].span());
+
+        let report = check_pokepaste("Nineteen Characters (Eevee)");
+        assert_eq!(report.pokemon[0].nickname, "");
+        assert_eq!(report.pokemon[0].name, "eevee");
+        assert!(matches!(report.errors[..], [ParseError::NicknameTooLong { .. }]));
+    }
+
+    #[test]
+    fn test_check_warnings() {
+        let paste = "Pikachu\nShiny: No\nPokeball: Poke Ball\nEVs: 4 HP / 8 Foo\n\
+                     Line that does nothing\nFavorite: Yes\n- Surf\n- surf\n";
+        let report = check_pokepaste(paste);
+        assert!(report.errors.is_empty());
+        let warnings: Vec<(String, &str)> = report
+            .warnings
+            .iter()
+            .map(|w| {
+                let span = w.span().unwrap();
+                (w.to_string(), &paste[span.start..span.end])
+            })
+            .collect();
+        assert_eq!(warnings, [
+            ("'Shiny: No' is the default and does nothing.".into(), "Shiny: No"),
+            ("Pokeball can't be packed and was dropped.".into(), "Pokeball: Poke Ball"),
+            ("Unknown stat 'Foo' was ignored.".into(), "Foo"),
+            (
+                "Unrecognized line 'Line that does nothing' was ignored.".into(),
+                "Line that does nothing"
+            ),
+            ("Unrecognized line 'Favorite: Yes' was ignored.".into(), "Favorite: Yes"),
+            ("surf is listed more than once.".into(), "surf"),
+        ]);
+
+        // warnings never change the set
+        assert_eq!(report.pokemon[0], parse_one(paste).unwrap());
+        assert_eq!(report.pokemon[0].moves.len(), 2);
+        assert_eq!(report.pokemon[0].evs.hp, 4);
+
+        let starts: Vec<usize> = report
+            .diagnostics()
+            .iter()
+            .map(|d| d.span.unwrap().start)
+            .collect();
+        assert_eq!(starts.len(), 6);
+        assert!(starts.is_sorted());
     }
 }
 
@@ -0,0 +1,155 @@
+/*
+* species.rs
+*
+* what the dex knows about a species besides its name: base stats, types,
+* abilities, weight and which species it's a forme of
+* dex/species.txt is written by dex_build/species.js from @pkmn/dex,
+* one species per line, keyed by the same id as dex/names.txt:
+*
+*   ID<TAB>HP/Atk/Def/SpA/SpD/Spe<TAB>Type[/Type]<TAB>Slot 0/Slot 1/Hidden<TAB>Weight<TAB>Base
+*   932	95/115/90/80/90/60	Fire/Dark	Blaze//Intimidate	830	932
+*
+* weight is in hectograms like showdown's weighthg, base is the id of the
+* base forme (the species itself if it is one)
+* a species that isn't in the file has no data, callers say so (stats
+* come back None, the calc fails with UnknownSpecies, speed tiers list it
+* as unknown), while the file is still empty that's every species and
+* build.rs warns about it
+*/
+
+use std::collections::HashMap;
+
+use arrayvec::ArrayVec;
+
+use crate::{
+    dex::{self, DexId},
+    error::DexError,
+    model::{Stats, Type},
+};
+
+// how showdown's packed format refers to ability slots
+pub const ABILITY_SLOTS: [&str; 3] = ["0", "1", "H"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Species {
+    pub base_stats:     Stats<u8>,
+    pub types:          ArrayVec<Type, 2>,
+    // slot 0, slot 1 and hidden, any of them can be missing
+    pub abilities:      [Option<DexId>; 3],
+    pub weight_hg:      u32,
+    pub base_species:   DexId,
+}
+
+impl Species {
+    pub fn has_ability(&self, ability: DexId) -> bool {
+        self.abilities.contains(&Some(ability))
+    }
+
+    // "0", "1" or "H"
+    pub fn ability_in_slot(&self, slot: &str) -> Option<DexId> {
+        let i = ABILITY_SLOTS.iter().position(|s| s.eq_ignore_ascii_case(slot))?;
+        self.abilities[i]
+    }
+}
+
+// abilities are looked up by name in abilities (a dex map)
+// with strict off, abilities the map doesn't have are left out instead of
+// failing, older gens don't know every hidden ability
+pub fn parse_species(
+    file: &str,
+    abilities: &HashMap<String, DexId>,
+    strict: bool
+) -> Result<HashMap<DexId, Species>, DexError> {
+    let mut species = HashMap::new();
+    for line in file.lines().filter(|line| !line.trim().is_empty()) {
+        let (id, entry) = parse_line(line, abilities, strict)
+            .ok_or_else(|| DexError::MalformedSpecies { line: line.to_string() })?;
+        species.insert(id, entry);
+    }
+    Ok(species)
+}
+
+fn parse_line(
+    line: &str,
+    abilities: &HashMap<String, DexId>,
+    strict: bool
+) -> Option<(DexId, Species)> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    let [id, stats, types, slots, weight, base] = fields[..] else {
+        return None;
+    };
+
+    let stats: Vec<u8> = stats
+        .split('/')
+        .map(|stat| stat.parse().ok())
+        .collect::<Option<_>>()?;
+    let types: Vec<Type> = types
+        .split('/')
+        .map(|t| t.parse().ok())
+        .collect::<Option<_>>()?;
+    let types = ArrayVec::try_from(types.as_slice()).ok()?;
+
+    let mut ability_ids = [None; 3];
+    let names: Vec<&str> = slots.split('/').collect();
+    if names.len() != ability_ids.len() {
+        return None;
+    }
+    for (slot, name) in ability_ids.iter_mut().zip(names) {
+        if name.is_empty() {
+            continue;
+        }
+        *slot = abilities.get(&dex::to_id(name)).copied();
+        if slot.is_none() && strict {
+            return None;
+        }
+    }
+
+    Some((id.parse().ok()?, Species {
+        base_stats:     Stats::from_array(stats.try_into().ok()?),
+        types,
+        abilities:      ability_ids,
+        weight_hg:      weight.parse().ok()?,
+        base_species:   base.parse().ok()?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_species() {
+        let dex = crate::get_dex();
+        let file = "932\t95/115/90/80/90/60\tFire/Dark\tBlaze//Intimidate\t830\t932\n\
+                    1311\t80/120/84/60/96/110\tGrass\tDefiant//\t398\t1311\n";
+        let species = parse_species(file, &dex.maps.abilities, true).unwrap();
+
+        let incineroar = &species[&932];
+        assert_eq!(incineroar.base_stats.atk, 115);
+        assert_eq!(incineroar.types.as_slice(), [Type::Fire, Type::Dark]);
+        assert_eq!(incineroar.ability_in_slot("H"), dex.maps.abilities.get("intimidate").copied());
+        assert_eq!(incineroar.ability_in_slot("1"), None);
+        assert!(incineroar.has_ability(dex.maps.abilities["blaze"]));
+        assert_eq!(species[&1311].types.as_slice(), [Type::Grass]);
+    }
+
+    #[test]
+    fn test_malformed_species() {
+        let dex = crate::get_dex();
+        let bad = [
+            "0\t45/49/49/65/65\tGrass/Poison\tOvergrow//Chlorophyll\t69\t0",
+            "0\t45/49/49/65/65/45\tGrass/Sound\tOvergrow//Chlorophyll\t69\t0",
+            "0\t45/49/49/65/65/45\tGrass/Poison\tOvergrow/Chlorophyll\t69\t0",
+            "0\t45/49/49/65/65/45\tGrass/Poison\tNot An Ability//\t69\t0",
+            "0\t45/49/49/65/65/45\tGrass/Poison\tOvergrow//\t69",
+        ];
+        for line in bad {
+            assert!(matches!(
+                parse_species(line, &dex.maps.abilities, true),
+                Err(DexError::MalformedSpecies { .. })
+            ), "{}", line);
+        }
+        let lenient = parse_species(bad[3], &dex.maps.abilities, false).unwrap();
+        assert_eq!(lenient[&0].abilities, [None; 3]);
+    }
+}
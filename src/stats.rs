@@ -0,0 +1,146 @@
+/*
+* stats.rs
+*
+* the stats a set actually ends up with, from base stats (species.rs),
+* IVs, EVs, level and nature, the same formulas as the games since gen 3
+*
+*   hp    = (2 * base + iv + ev / 4) * level / 100 + level + 10
+*   other = ((2 * base + iv + ev / 4) * level / 100 + 5) * nature
+*
+* every division rounds down, nature is 1.1, 0.9 or 1
+*/
+
+use crate::{
+    binary::PokemonBin,
+    codec,
+    dex::Dex,
+    model::{Nature, Stat, Stats, MAX_LEVEL},
+    parser::Pokemon,
+};
+
+pub fn calc_stat(
+    stat: Stat,
+    base: u8,
+    iv: u8,
+    ev: u8,
+    level: u8,
+    nature: Option<Nature>
+) -> u16 {
+    let (base, iv, ev, level) = (base as u32, iv as u32, ev as u32, level as u32);
+    let core = (2 * base + iv + ev / 4) * level / 100;
+    if stat == Stat::Hp {
+        // shedinja always has 1 hp
+        if base == 1 {
+            return 1;
+        }
+        return (core + level + 10) as u16;
+    }
+    let value = core + 5;
+    let value = match nature.and_then(Nature::effect) {
+        Some((plus, _)) if plus == stat => value * 110 / 100,
+        Some((_, minus)) if minus == stat => value * 90 / 100,
+        _ => value,
+    };
+    value as u16
+}
+
+// multiplies by modifier / 4096, rounding half down like the games
+// 6144 is 1.5x, 2048 is 0.5x
+pub fn apply_mod(value: u32, modifier: u32) -> u32 {
+    let scaled = value * modifier;
+    scaled / 4096 + u32::from(scaled % 4096 > 2048)
+}
+
+// several modifiers on the same value are chained into one first
+pub fn chain_mods(modifiers: impl IntoIterator<Item = u32>) -> u32 {
+    modifiers
+        .into_iter()
+        .fold(4096, |chain, modifier| (chain * modifier + 2048) >> 12)
+}
+
+pub fn calc_stats(
+    base: &Stats<u8>,
+    ivs: &Stats<u8>,
+    evs: &Stats<u8>,
+    level: u8,
+    nature: Option<Nature>
+) -> Stats<u16> {
+    let mut stats = Stats::default();
+    for stat in Stat::ALL {
+        stats[stat] = calc_stat(stat, base[stat], ivs[stat], evs[stat], level, nature);
+    }
+    stats
+}
+
+// None when the dex has no base stats for the species
+// no level in the set means level 100
+pub fn pokemon_stats(dex: &Dex, pokemon: &Pokemon) -> Option<Stats<u16>> {
+    let species = dex.species_data(&pokemon.name)?;
+    Some(calc_stats(
+        &species.base_stats,
+        &pokemon.ivs,
+        &pokemon.evs,
+        pokemon.level.unwrap_or(MAX_LEVEL),
+        pokemon.nature,
+    ))
+}
+
+// decoded with the same dex first, the ids only mean something to it
+pub fn pokemon_bin_stats(dex: &Dex, pbin: &PokemonBin) -> Option<Stats<u16>> {
+    pokemon_stats(dex, &codec::pokebin_to_string(dex, pbin))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dex::Generation, parser, species};
+
+    // common spreads, worked out by hand
+    #[test]
+    fn test_calc_stats() {
+        let garchomp = Stats::from_array([108, 130, 95, 80, 85, 102]);
+        let evs = Stats::from_array([0, 252, 0, 0, 4, 252]);
+        let stats = calc_stats(&garchomp, &Stats::splat(31), &evs, 100, Some(Nature::Jolly));
+        assert_eq!(stats.to_array(), [357, 359, 226, 176, 207, 333]);
+
+        let incineroar = Stats::from_array([95, 115, 90, 80, 90, 60]);
+        let evs = Stats::from_array([252, 4, 0, 0, 252, 0]);
+        let mut ivs = Stats::splat(31);
+        ivs.spe = 0;
+        let stats = calc_stats(&incineroar, &ivs, &evs, 50, Some(Nature::Careful));
+        assert_eq!(stats.to_array(), [202, 136, 110, 90, 156, 65]);
+
+        assert_eq!(calc_stat(Stat::Hp, 1, 31, 252, 100, None), 1);
+    }
+
+    #[test]
+    fn test_pokemon_stats() {
+        let mut dex = Dex::build(Generation::Gen9).unwrap();
+        let line = format!(
+            "{}\t108/130/95/80/85/102\tDragon/Ground\tSand Veil//Rough Skin\t950\t{0}",
+            dex.maps.names["garchomp"]
+        );
+        dex.species = species::parse_species(&line, &dex.maps.abilities, true).unwrap();
+
+        let paste = "Garchomp\nLevel: 50\nEVs: 252 Atk / 4 SpD / 252 Spe\nJolly Nature\n\n\
+                     Pikachu";
+        let team = parser::parse_pokepaste(paste.to_string()).unwrap();
+        let stats = pokemon_stats(&dex, &team[0]).unwrap();
+        assert_eq!(stats.to_array(), [183, 182, 115, 90, 106, 169]);
+        // nothing known about pikachu here
+        assert_eq!(pokemon_stats(&dex, &team[1]), None);
+
+        let pbin = codec::encoded_pokemon(&dex, &team[0], 0).unwrap();
+        assert_eq!(pokemon_bin_stats(&dex, &pbin), Some(stats));
+    }
+    #[test]
+    #[ignore = "needs dex/species.txt, run `npm run species` in dex_build"]
+    fn test_shipped_base_stats() {
+        let dex = crate::get_dex();
+        let paste = "Incineroar\nLevel: 50\nEVs: 252 HP / 4 Atk / 252 SpD\nCareful Nature\n\
+                     IVs: 0 Spe";
+        let team = parser::parse_pokepaste(paste.to_string()).unwrap();
+        let stats = pokemon_stats(dex, &team[0]).unwrap();
+        assert_eq!(stats.to_array(), [202, 136, 110, 90, 156, 65]);
+    }
+}
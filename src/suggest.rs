@@ -0,0 +1,105 @@
+/*
+* suggest.rs
+*
+* "did you mean" for names that aren't in the dex
+* both sides go through showdown's toID first, so "Life orb." and
+* "Flutter-Mane" are exact matches, anything else is ranked by edit distance
+*/
+
+use std::collections::HashMap;
+
+use crate::dex::{self, DexId};
+
+// how many suggestions we hand back by default
+pub const MAX_SUGGESTIONS: usize = 3;
+
+// best matches first, in their original case from the tables
+pub fn suggest(
+    table: &HashMap<DexId, String>,
+    query: &str,
+    limit: usize
+) -> Vec<String> {
+    let query_id = dex::to_id(query);
+    if query_id.is_empty() {
+        return Vec::new();
+    }
+    // anything further away than this is noise
+    let max_distance = (query_id.len() / 3).max(2);
+
+    let mut ranked: Vec<(usize, bool, &String)> = table
+        .values()
+        .filter(|name| !name.is_empty())
+        .filter_map(|name| {
+            let id = dex::to_id(name);
+            let distance = edit_distance(&query_id, &id);
+            // "urshifu" should still find "Urshifu-Rapid-Strike"
+            let prefix = id.starts_with(&query_id);
+            if distance <= max_distance || prefix {
+                Some((distance, !prefix, name))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    // closest first, prefixes win ties, then alphabetical so it's stable
+    ranked.sort();
+    ranked
+        .into_iter()
+        .take(limit)
+        .map(|(_, _, name)| name.clone())
+        .collect()
+}
+
+// levenshtein distance, ids are ascii so bytes are fine
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        curr[0] = i;
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            curr[j] = (prev[j] + 1)
+                .min(curr[j - 1] + 1)
+                .min(prev[j - 1] + cost);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("gholdngo", "gholdengo"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let dex = crate::get_dex();
+        let top = |table, query| suggest(table, query, MAX_SUGGESTIONS)[0].clone();
+        assert_eq!(top(&dex.tables.names, "Gholdngo"), "Gholdengo");
+        assert_eq!(top(&dex.tables.names, "Flutter-Mane"), "Flutter Mane");
+        assert_eq!(
+            top(&dex.tables.names, "Urshifu Rapid Strike"),
+            "Urshifu-Rapid-Strike"
+        );
+        assert_eq!(top(&dex.tables.items, "Life orb."), "Life Orb");
+    }
+
+    #[test]
+    fn test_no_suggestions_for_garbage() {
+        let dex = crate::get_dex();
+        assert!(suggest(&dex.tables.moves, "qqqqqqqqqqqq", 3).is_empty());
+        assert!(suggest(&dex.tables.moves, "!!", 3).is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+/*
+* typechart.rs
+*
+* how much each type takes from each other type, the chart hasn't changed
+* since gen 6 so every generation we pack shares it
+* dex/typechart.txt was typed in from showdown's damageTaken, `npm start`
+* in dex_build (index.js) writes the same file from @pkmn/dex, one line
+* per defending type:
+*
+*   Fire	020102001012200022
+*
+* one digit per attacking type in Type::ALL order (stellar isn't in it):
+* 0 = neutral, 1 = super effective, 2 = resisted, 3 = immune
+*/
+
+use std::ops::{Index, IndexMut};
+
+use serde::{ser::SerializeMap, Serialize, Serializer};
+
+use crate::{error::DexError, model::Type};
+
+// the types in the chart, every type but stellar
+pub const CHART_TYPES: [Type; 18] = {
+    let mut types = [Type::Normal; 18];
+    let mut i = 0;
+    while i < 18 {
+        types[i] = Type::ALL[i];
+        i += 1;
+    }
+    types
+};
+
+// one value per chart type, serializes as { "Normal": .., "Fire": .. }
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TypeTable<T>(pub [T; 18]);
+
+impl<T: Copy> TypeTable<T> {
+    pub fn from_fn(f: impl Fn(Type) -> T) -> Self {
+        TypeTable(CHART_TYPES.map(f))
+    }
+}
+
+// stellar has no place in the chart, indexing with it panics
+impl<T> Index<Type> for TypeTable<T> {
+    type Output = T;
+
+    fn index(&self, t: Type) -> &T {
+        &self.0[t as usize]
+    }
+}
+
+impl<T> IndexMut<Type> for TypeTable<T> {
+    fn index_mut(&mut self, t: Type) -> &mut T {
+        &mut self.0[t as usize]
+    }
+}
+
+impl<T: Serialize> Serialize for TypeTable<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(CHART_TYPES.len()))?;
+        for (t, value) in CHART_TYPES.iter().zip(&self.0) {
+            map.serialize_entry(t.name(), value)?;
+        }
+        map.end()
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TypeChart {
+    // [defending][attacking], the digits from the file
+    taken: [[u8; 18]; 18],
+}
+
+impl TypeChart {
+    pub fn parse(file: &str) -> Result<Self, DexError> {
+        let mut chart = TypeChart::default();
+        let mut seen = [false; 18];
+        for line in file.lines().filter(|line| !line.trim().is_empty()) {
+            let malformed = || DexError::MalformedTypeChart { line: line.to_string() };
+            let (name, digits) = line.split_once('\t').ok_or_else(malformed)?;
+            let defense: Type = name.parse().map_err(|_| malformed())?;
+            if defense == Type::Stellar || digits.len() != 18 {
+                return Err(malformed());
+            }
+            let row = &mut chart.taken[defense as usize];
+            for (taken, digit) in row.iter_mut().zip(digits.bytes()) {
+                *taken = match digit {
+                    b'0'..=b'3' => digit - b'0',
+                    _ => return Err(malformed()),
+                };
+            }
+            seen[defense as usize] = true;
+        }
+        if let Some(missing) = seen.iter().position(|seen| !seen) {
+            return Err(DexError::MalformedTypeChart {
+                line: format!("no line for {}", CHART_TYPES[missing]),
+            });
+        }
+        Ok(chart)
+    }
+
+    // 0, 0.25, 0.5, 1, 2 or 4
+    // a stellar attack (tera blast) is neutral against everything
+    pub fn effectiveness(&self, attack: Type, defense: &[Type]) -> f32 {
+        if attack == Type::Stellar {
+            return 1.0;
+        }
+        defense
+            .iter()
+            .filter(|&&t| t != Type::Stellar)
+            .map(|&t| match self.taken[t as usize][attack as usize] {
+                1 => 2.0,
+                2 => 0.5,
+                3 => 0.0,
+                _ => 1.0,
+            })
+            .product()
+    }
+
+    // what every attacking type does to something with these types
+    pub fn defense(&self, defense: &[Type]) -> TypeTable<f32> {
+        TypeTable::from_fn(|attack| self.effectiveness(attack, defense))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_effectiveness() {
+        let chart = &crate::get_dex().type_chart;
+        assert_eq!(chart.effectiveness(Type::Dark, &[Type::Ghost, Type::Psychic]), 4.0);
+        assert_eq!(chart.effectiveness(Type::Ground, &[Type::Flying, Type::Steel]), 0.0);
+        assert_eq!(chart.effectiveness(Type::Fire, &[Type::Water, Type::Dragon]), 0.25);
+        assert_eq!(chart.effectiveness(Type::Dragon, &[Type::Fairy]), 0.0);
+        assert_eq!(chart.effectiveness(Type::Stellar, &[Type::Fairy]), 1.0);
+
+        let defense = chart.defense(&[Type::Fire, Type::Dark]);
+        assert_eq!(defense[Type::Psychic], 0.0);
+        assert_eq!(defense[Type::Ground], 2.0);
+        assert_eq!(defense[Type::Ghost], 0.5);
+    }
+
+    // the chart was typed in by hand, the totals catch a slipped digit
+    // 51 super effective, 61 resisted and 8 immune pairs since gen 6
+    #[test]
+    fn test_chart_totals() {
+        let chart = &crate::get_dex().type_chart;
+        let mut counts = [0; 4];
+        for attack in CHART_TYPES {
+            for defense in CHART_TYPES {
+                let i = match chart.effectiveness(attack, &[defense]) {
+                    2.0 => 1,
+                    0.5 => 2,
+                    0.0 => 3,
+                    _ => 0,
+                };
+                counts[i] += 1;
+            }
+        }
+        assert_eq!(counts, [204, 51, 61, 8]);
+    }
+
+    #[test]
+    fn test_malformed_chart() {
+        let file = include_str!("../dex/typechart.txt");
+        assert!(TypeChart::parse(file).is_ok());
+        assert!(TypeChart::parse(&file.replace("Fire\t", "Sound\t")).is_err());
+        assert!(TypeChart::parse(&file.replacen('3', "4", 1)).is_err());
+        let without_fairy = file.lines().take(17).collect::<Vec<_>>().join("\n");
+        assert!(TypeChart::parse(&without_fairy).is_err());
+    }
+
+    #[test]
+    fn test_type_table_shape() {
+        let table = TypeTable::from_fn(|t| t as u8);
+        let json = serde_json::to_value(table).unwrap();
+        assert_eq!(json["Normal"], 0);
+        assert_eq!(json["Fairy"], 17);
+        assert!(json.get("Stellar").is_none());
+    }
+}
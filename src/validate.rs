@@ -0,0 +1,641 @@
+/*
+* validate.rs
+*
+* is a team actually allowed in a format, the stuff the parser doesn't care
+* about: EV totals, clauses, bans, restricted legends, abilities and moves
+* the species can't have (see species.rs and learnset.rs)
+* a Ruleset is one showdown format, validate_team lists everything wrong
+* with a team instead of stopping at the first problem
+*
+* the ban lists are a snapshot of showdown's, tiers move so expect to
+* update them now and then
+*/
+
+use std::{collections::HashSet, fmt};
+
+use crate::{
+    dex::{self, Dex, DexId, EntryKind, Generation},
+    error::DexError,
+    model::{MAX_EV, MAX_IV, MIN_LEVEL, STAT_LABELS},
+    parser::Pokemon,
+};
+
+// the most EVs a pokemon can have across all six stats
+pub const MAX_EV_TOTAL: u16 = 510;
+
+// names in the lists below are canonical dex names
+// "Arceus-*" means every forme of Arceus, including plain Arceus
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ruleset {
+    // showdown format ids this applies to, "gen9ou"
+    pub ids:                &'static [&'static str],
+    pub name:               &'static str,
+    pub generation:         Generation,
+    pub min_team_size:      usize,
+    pub max_team_size:      usize,
+    pub max_level:          u8,
+    // no two pokemon of the same species
+    pub species_clause:     bool,
+    // no two pokemon holding the same item
+    pub item_clause:        bool,
+    // at most max_restricted of these on one team
+    pub restricted:         &'static [&'static str],
+    pub max_restricted:     usize,
+    pub banned_species:     &'static [&'static str],
+    // anything with one of these in its name, megas and the like
+    pub banned_formes:      &'static [&'static str],
+    pub banned_items:       &'static [&'static str],
+    pub banned_abilities:   &'static [&'static str],
+    pub banned_moves:       &'static [&'static str],
+}
+
+impl Ruleset {
+    pub const ALL: [&'static Ruleset; 3] = [&GEN9_OU, &GEN9_VGC_REG_G, &GEN9_VGC_REG_H];
+
+    // by showdown format id, "[Gen 9] OU" and "gen9ou" both work
+    pub fn for_format(format: &str) -> Result<&'static Ruleset, DexError> {
+        let id = dex::to_id(format);
+        Ruleset::ALL
+            .into_iter()
+            .find(|rules| rules.ids.contains(&id.as_str()) || dex::to_id(rules.name) == id)
+            .ok_or_else(|| DexError::UnsupportedFormat { format: format.to_string() })
+    }
+}
+
+// slot is the pokemon's place in the team, from 0 like Span::block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    TeamSize { count: usize, min: usize, max: usize },
+    Level { slot: usize, level: u8, max: u8 },
+    EvTooHigh { slot: usize, stat: &'static str, value: u8 },
+    EvTotal { slot: usize, total: u16 },
+    IvTooHigh { slot: usize, stat: &'static str, value: u8 },
+    // not in the dex for the format's generation
+    UnknownEntry { slot: usize, kind: EntryKind, name: String },
+    Banned { slot: usize, kind: EntryKind, name: String },
+    TooManyRestricted { count: usize, max: usize },
+    // slot is the second one, the first is fine on its own
+    SpeciesClause { slot: usize, name: String },
+    ItemClause { slot: usize, name: String },
+    // not one of the species' abilities
+    IllegalAbility { slot: usize, species: String, name: String },
+    // not in the species' learnset for the format's generation
+    IllegalMove { slot: usize, species: String, name: String },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::TeamSize { count, min, max } => {
+                write!(f, "Team has {} Pokémon, it needs {} to {}.", count, min, max)
+            },
+            Violation::Level { slot, level, max } => {
+                write!(
+                    f,
+                    "Pokémon #{} is level {}, the limit is {}.",
+                    slot + 1,
+                    level,
+                    max
+                )
+            },
+            Violation::EvTooHigh { slot, stat, value } => {
+                write!(
+                    f,
+                    "Pokémon #{} has {} {} EVs, the most is {}.",
+                    slot + 1,
+                    value,
+                    stat,
+                    MAX_EV
+                )
+            },
+            Violation::EvTotal { slot, total } => {
+                write!(
+                    f,
+                    "Pokémon #{} has {} EVs in total, the most is {}.",
+                    slot + 1,
+                    total,
+                    MAX_EV_TOTAL
+                )
+            },
+            Violation::IvTooHigh { slot, stat, value } => {
+                write!(
+                    f,
+                    "Pokémon #{} has {} {} IVs, the most is {}.",
+                    slot + 1,
+                    value,
+                    stat,
+                    MAX_IV
+                )
+            },
+            Violation::UnknownEntry { slot, kind, name } => {
+                write!(f, "Pokémon #{} has unknown {} '{}'.", slot + 1, kind, name)
+            },
+            Violation::Banned { slot, kind, name } => {
+                write!(f, "Pokémon #{}: {} {} is banned.", slot + 1, kind, name)
+            },
+            Violation::TooManyRestricted { count, max } => {
+                write!(
+                    f,
+                    "Team has {} restricted Pokémon, only {} allowed.",
+                    count,
+                    max
+                )
+            },
+            Violation::SpeciesClause { slot, name } => {
+                write!(
+                    f,
+                    "Pokémon #{} is a second {} (Species Clause).",
+                    slot + 1,
+                    name
+                )
+            },
+            Violation::ItemClause { slot, name } => {
+                write!(
+                    f,
+                    "Pokémon #{} holds a second {} (Item Clause).",
+                    slot + 1,
+                    name
+                )
+            },
+            Violation::IllegalAbility { slot, species, name } => {
+                write!(f, "Pokémon #{}: {} can't have {}.", slot + 1, species, name)
+            },
+            Violation::IllegalMove { slot, species, name } => {
+                write!(f, "Pokémon #{}: {} can't learn {}.", slot + 1, species, name)
+            },
+        }
+    }
+}
+
+// everything wrong with team under rules, empty if it's legal
+// names are looked up in dex, which should be the one for rules.generation
+pub fn validate_team(dex: &Dex, team: &[Pokemon], rules: &Ruleset) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    let count = team.len();
+    if count < rules.min_team_size || count > rules.max_team_size {
+        violations.push(Violation::TeamSize {
+            count,
+            min: rules.min_team_size,
+            max: rules.max_team_size,
+        });
+    }
+
+    // species and items already on the team, for the clauses
+    let mut species_seen: HashSet<DexId> = HashSet::new();
+    let mut items_seen: HashSet<DexId> = HashSet::new();
+    let mut restricted = 0;
+
+    for (slot, pokemon) in team.iter().enumerate() {
+        check_spread(&mut violations, slot, pokemon, rules);
+
+        let species = check_entry(
+            &mut violations, dex, slot, EntryKind::Species, &pokemon.name, rules.banned_species
+        );
+        if let Some((id, species)) = species {
+            let forme_banned = rules
+                .banned_formes
+                .iter()
+                .any(|forme| species.contains(forme));
+            if forme_banned {
+                violations.push(Violation::Banned {
+                    slot,
+                    kind: EntryKind::Species,
+                    name: species.to_string(),
+                });
+            }
+            if is_listed(rules.restricted, species) {
+                restricted += 1;
+            }
+            // formes count as their base species, Rotom-Wash is still a Rotom
+            // that needs species.txt, without it a forme is its own species
+            // and Rotom plus Rotom-Wash gets through
+            let base = dex.species.get(&id).map_or(id, |data| data.base_species);
+            if rules.species_clause && !species_seen.insert(base) {
+                violations.push(Violation::SpeciesClause {
+                    slot,
+                    name: species.to_string(),
+                });
+            }
+        }
+
+        if let Some((id, item)) = check_entry(
+            &mut violations, dex, slot, EntryKind::Item, &pokemon.item, rules.banned_items
+        ) {
+            if rules.item_clause && !items_seen.insert(id) {
+                violations.push(Violation::ItemClause {
+                    slot,
+                    name: item.to_string(),
+                });
+            }
+        }
+
+        let ability = check_entry(
+            &mut violations,
+            dex,
+            slot,
+            EntryKind::Ability,
+            dex.resolve_ability(&pokemon.name, &pokemon.ability),
+            rules.banned_abilities
+        );
+        if let (Some((species_id, species)), Some((ability_id, name))) = (species, ability) {
+            let allowed = dex.species.get(&species_id).map(|data| data.has_ability(ability_id));
+            if allowed == Some(false) {
+                violations.push(Violation::IllegalAbility {
+                    slot,
+                    species: species.to_string(),
+                    name: name.to_string(),
+                });
+            }
+        }
+        for m in &pokemon.moves {
+            let found = check_entry(
+                &mut violations, dex, slot, EntryKind::Move, m.name(), rules.banned_moves
+            );
+            let (Some((species_id, species)), Some((move_id, name))) = (species, found) else {
+                continue;
+            };
+            if dex.can_learn(species_id, move_id) == Some(false) {
+                violations.push(Violation::IllegalMove {
+                    slot,
+                    species: species.to_string(),
+                    name: name.to_string(),
+                });
+            }
+        }
+    }
+
+    if restricted > rules.max_restricted {
+        violations.push(Violation::TooManyRestricted {
+            count: restricted,
+            max: rules.max_restricted,
+        });
+    }
+    violations
+}
+
+// level, EVs and IVs, a Pokemon from the parser is already inside the
+// per stat limits but one built by hand or from json might not be
+fn check_spread(
+    violations: &mut Vec<Violation>,
+    slot: usize,
+    pokemon: &Pokemon,
+    rules: &Ruleset
+) {
+    // no level means level 100, VGC formats scale everyone down to 50
+    let level = pokemon.level.unwrap_or(100);
+    if !(MIN_LEVEL..=rules.max_level).contains(&level) {
+        violations.push(Violation::Level { slot, level, max: rules.max_level });
+    }
+
+    let evs = pokemon.evs.to_array();
+    for (stat, value) in STAT_LABELS.into_iter().zip(evs) {
+        if value > MAX_EV {
+            violations.push(Violation::EvTooHigh { slot, stat, value });
+        }
+    }
+    let total: u16 = evs.iter().map(|&ev| ev as u16).sum();
+    if total > MAX_EV_TOTAL {
+        violations.push(Violation::EvTotal { slot, total });
+    }
+
+    for (stat, value) in STAT_LABELS.into_iter().zip(pokemon.ivs.to_array()) {
+        if value > MAX_IV {
+            violations.push(Violation::IvTooHigh { slot, stat, value });
+        }
+    }
+}
+
+// looks name up and checks it against banned
+// the id and canonical name if it's in the dex, empty names are skipped
+fn check_entry<'a>(
+    violations: &mut Vec<Violation>,
+    dex: &'a Dex,
+    slot: usize,
+    kind: EntryKind,
+    name: &str,
+    banned: &[&str]
+) -> Option<(DexId, &'a str)> {
+    if name.is_empty() {
+        return None;
+    }
+    let Some(&id) = dex.maps.get(kind).get(&dex::to_id(name)) else {
+        violations.push(Violation::UnknownEntry {
+            slot,
+            kind,
+            name: name.to_string(),
+        });
+        return None;
+    };
+    let canonical = dex.tables.get(kind).get(&id)?.as_str();
+    if is_listed(banned, canonical) {
+        violations.push(Violation::Banned {
+            slot,
+            kind,
+            name: canonical.to_string(),
+        });
+    }
+    Some((id, canonical))
+}
+
+fn is_listed(list: &[&str], name: &str) -> bool {
+    list.iter().any(|entry| match entry.strip_suffix("-*") {
+        Some(base) => {
+            name == base || name.strip_prefix(base).is_some_and(|rest| rest.starts_with('-'))
+        },
+        None => *entry == name,
+    })
+}
+
+// rulesets -------------------------------------------------------------------
+
+// formes that only exist mid battle, or not at all in scarlet and violet
+const GEN9_BANNED_FORMES: &[&str] = &[
+    "-Mega", "-Primal", "-Gmax", "-Eternamax", "-Totem", "-Starter",
+    "-Ultra", "-Tera", "-Stellar",
+];
+
+const GEN9_RESTRICTED: &[&str] = &[
+    "Mewtwo", "Lugia", "Ho-Oh", "Kyogre", "Groudon", "Rayquaza",
+    "Dialga-*", "Palkia-*", "Giratina-*", "Reshiram", "Zekrom", "Kyurem-*",
+    "Cosmog", "Cosmoem", "Solgaleo", "Lunala", "Necrozma-*",
+    "Zacian-*", "Zamazenta-*", "Eternatus", "Calyrex-*",
+    "Koraidon", "Miraidon", "Terapagos-*",
+];
+
+const GEN9_MYTHICAL: &[&str] = &[
+    "Mew", "Celebi", "Jirachi", "Deoxys-*", "Phione", "Manaphy", "Darkrai",
+    "Shaymin-*", "Arceus-*", "Victini", "Keldeo-*", "Meloetta-*", "Genesect-*",
+    "Diancie", "Hoopa-*", "Volcanion", "Magearna-*", "Marshadow", "Zeraora",
+    "Meltan", "Melmetal", "Zarude-*", "Pecharunt",
+];
+
+pub const GEN9_OU: Ruleset = Ruleset {
+    ids:                &["gen9ou"],
+    name:               "[Gen 9] OU",
+    generation:         Generation::Gen9,
+    min_team_size:      1,
+    max_team_size:      6,
+    max_level:          100,
+    species_clause:     true,
+    item_clause:        false,
+    restricted:         &[],
+    max_restricted:     0,
+    banned_species:     &[
+        "Annihilape", "Arceus-*", "Baxcalibur", "Calyrex-Ice", "Calyrex-Shadow",
+        "Chi-Yu", "Chien-Pao", "Deoxys", "Deoxys-Attack", "Dialga-*",
+        "Espathra", "Eternatus", "Flutter Mane", "Giratina-*", "Gouging Fire",
+        "Groudon", "Ho-Oh", "Houndstone", "Iron Bundle", "Koraidon", "Kyogre",
+        "Kyurem-Black", "Kyurem-White", "Landorus", "Lugia", "Lunala",
+        "Magearna-*", "Mewtwo", "Miraidon", "Necrozma-Dawn-Wings",
+        "Necrozma-Dusk-Mane", "Ogerpon-Hearthflame", "Palafin", "Palkia-*",
+        "Rayquaza", "Regieleki", "Reshiram", "Roaring Moon", "Shaymin-Sky",
+        "Solgaleo", "Spectrier", "Terapagos-*", "Ursaluna-Bloodmoon",
+        "Urshifu", "Zacian-*", "Zamazenta-*", "Zekrom",
+    ],
+    banned_formes:      GEN9_BANNED_FORMES,
+    banned_items:       &["Bright Powder", "King's Rock", "Lax Incense", "Razor Fang"],
+    banned_abilities:   &["Arena Trap", "Moody", "Sand Veil", "Shadow Tag", "Snow Cloak"],
+    // baton pass, evasion and ohko clauses are just bans here
+    banned_moves:       &[
+        "Baton Pass", "Last Respects", "Shed Tail",
+        "Double Team", "Minimize",
+        "Fissure", "Guillotine", "Horn Drill", "Sheer Cold",
+    ],
+};
+
+// one restricted legend allowed, no mythicals
+pub const GEN9_VGC_REG_G: Ruleset = Ruleset {
+    ids:                &["gen9vgc2024regg", "gen9vgc2025regg"],
+    name:               "[Gen 9] VGC 2024 Reg G",
+    generation:         Generation::Gen9,
+    min_team_size:      4,
+    max_team_size:      6,
+    max_level:          100,
+    species_clause:     true,
+    item_clause:        true,
+    restricted:         GEN9_RESTRICTED,
+    max_restricted:     1,
+    banned_species:     GEN9_MYTHICAL,
+    banned_formes:      GEN9_BANNED_FORMES,
+    banned_items:       &[],
+    banned_abilities:   &[],
+    banned_moves:       &[],
+};
+
+// no legends at all, and no paradox pokemon either
+pub const GEN9_VGC_REG_H: Ruleset = Ruleset {
+    ids:                &["gen9vgc2024regh", "gen9vgc2025regh"],
+    name:               "[Gen 9] VGC 2024 Reg H",
+    max_restricted:     0,
+    banned_species:     &[
+        // mythicals
+        "Mew", "Celebi", "Jirachi", "Deoxys-*", "Phione", "Manaphy", "Darkrai",
+        "Shaymin-*", "Arceus-*", "Victini", "Keldeo-*", "Meloetta-*",
+        "Genesect-*", "Diancie", "Hoopa-*", "Volcanion", "Magearna-*",
+        "Marshadow", "Zeraora", "Meltan", "Melmetal", "Zarude-*", "Pecharunt",
+        // legends that aren't restricted
+        "Articuno-*", "Zapdos-*", "Moltres-*", "Raikou", "Entei", "Suicune",
+        "Regirock", "Regice", "Registeel", "Latias", "Latios",
+        "Uxie", "Mesprit", "Azelf", "Heatran", "Regigigas", "Cresselia",
+        "Cobalion", "Terrakion", "Virizion", "Tornadus-*", "Thundurus-*",
+        "Landorus-*", "Enamorus-*", "Type: Null", "Silvally-*",
+        "Tapu Koko", "Tapu Lele", "Tapu Bulu", "Tapu Fini",
+        "Kubfu", "Urshifu-*", "Regieleki", "Regidrago", "Glastrier",
+        "Spectrier", "Wo-Chien", "Chien-Pao", "Ting-Lu", "Chi-Yu",
+        "Okidogi", "Munkidori", "Fezandipiti", "Ogerpon-*",
+        // paradox
+        "Great Tusk", "Scream Tail", "Brute Bonnet", "Flutter Mane",
+        "Slither Wing", "Sandy Shocks", "Roaring Moon", "Walking Wake",
+        "Gouging Fire", "Raging Bolt", "Iron Treads", "Iron Bundle",
+        "Iron Hands", "Iron Jugulis", "Iron Moth", "Iron Thorns",
+        "Iron Valiant", "Iron Leaves", "Iron Boulder", "Iron Crown",
+    ],
+    ..GEN9_VGC_REG_G
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    fn team(paste: &str) -> Vec<Pokemon> {
+        parser::parse_pokepaste(paste.to_string()).unwrap()
+    }
+
+    // a typo in a ban list would quietly allow the pokemon
+    #[test]
+    fn test_listed_names_are_in_the_dex() {
+        let dex = crate::get_dex();
+        for rules in Ruleset::ALL {
+            let lists = [
+                (EntryKind::Species, rules.restricted),
+                (EntryKind::Species, rules.banned_species),
+                (EntryKind::Item, rules.banned_items),
+                (EntryKind::Ability, rules.banned_abilities),
+                (EntryKind::Move, rules.banned_moves),
+            ];
+            for (kind, list) in lists {
+                for entry in list {
+                    let name = entry.strip_suffix("-*").unwrap_or(entry);
+                    assert!(
+                        dex.maps.get(kind).contains_key(&dex::to_id(name)),
+                        "{}: {} {}", rules.name, kind, entry
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_for_format() {
+        assert_eq!(Ruleset::for_format("gen9ou").unwrap(), &GEN9_OU);
+        assert_eq!(Ruleset::for_format("[Gen 9] OU").unwrap(), &GEN9_OU);
+        assert_eq!(Ruleset::for_format("gen9vgc2025regh").unwrap(), &GEN9_VGC_REG_H);
+        assert!(Ruleset::for_format("gen1ou").is_err());
+    }
+
+    #[test]
+    fn test_legal_team() {
+        let dex = crate::get_dex();
+        let team = team(
+            "Great Tusk @ Booster Energy\nAbility: Protosynthesis\n\
+             EVs: 252 Atk / 4 SpD / 252 Spe\n- Headlong Rush\n- Rapid Spin\n\n\
+             Gholdengo @ Choice Scarf\nAbility: Good as Gold\n- Make It Rain"
+        );
+        assert_eq!(validate_team(dex, &team, &GEN9_OU), []);
+    }
+
+    #[test]
+    fn test_spread_violations() {
+        let dex = crate::get_dex();
+        let mut team = team("Pikachu\nEVs: 252 HP / 252 Atk / 252 Spe");
+        team[0].ivs.spe = 40;
+        team[0].evs.def = 255;
+        team[0].level = Some(101);
+        let violations = validate_team(dex, &team, &GEN9_OU);
+        assert_eq!(violations, [
+            Violation::Level { slot: 0, level: 101, max: 100 },
+            Violation::EvTooHigh { slot: 0, stat: "Def", value: 255 },
+            Violation::EvTotal { slot: 0, total: 1011 },
+            Violation::IvTooHigh { slot: 0, stat: "Spe", value: 40 },
+        ]);
+    }
+
+    #[test]
+    fn test_bans_and_clauses() {
+        let dex = crate::get_dex();
+        let team = team(
+            "Flutter Mane @ Leftovers\n- Moonblast\n\n\
+             Pikachu @ Leftovers\nAbility: Moody\n- Baton Pass\n\n\
+             Pikachu\n- Thunderbolt\n- Spore Blast\n\n\
+             Arceus-Fairy\n\nCharizard-Mega-X"
+        );
+        let violations = validate_team(dex, &team, &GEN9_OU);
+        let banned = |slot, kind, name: &str| Violation::Banned { slot, kind, name: name.into() };
+        assert_eq!(violations, [
+            banned(0, EntryKind::Species, "Flutter Mane"),
+            banned(1, EntryKind::Ability, "Moody"),
+            banned(1, EntryKind::Move, "Baton Pass"),
+            Violation::SpeciesClause { slot: 2, name: "Pikachu".into() },
+            Violation::UnknownEntry { slot: 2, kind: EntryKind::Move, name: "Spore Blast".into() },
+            banned(3, EntryKind::Species, "Arceus-Fairy"),
+            banned(4, EntryKind::Species, "Charizard-Mega-X"),
+        ]);
+
+        // no item clause in OU, but there is in VGC
+        let vgc = validate_team(dex, &team[..3], &GEN9_VGC_REG_G);
+        assert!(vgc.contains(&Violation::TeamSize { count: 3, min: 4, max: 6 }));
+        assert!(vgc.contains(&Violation::ItemClause { slot: 1, name: "Leftovers".into() }));
+    }
+
+    #[test]
+    fn test_restricted() {
+        let dex = crate::get_dex();
+        let team = team(
+            "Koraidon\n\nCalyrex-Shadow\n\nIncineroar\n\nRillaboom\n\nAmoonguss"
+        );
+        let violations = validate_team(dex, &team, &GEN9_VGC_REG_G);
+        assert_eq!(violations, [Violation::TooManyRestricted { count: 2, max: 1 }]);
+        assert_eq!(validate_team(dex, &team[1..], &GEN9_VGC_REG_G), []);
+
+        let reg_h = validate_team(dex, &team[2..], &GEN9_VGC_REG_H);
+        assert_eq!(reg_h, [Violation::TeamSize { count: 3, min: 4, max: 6 }]);
+    }
+
+    #[test]
+    fn test_illegal_moves() {
+        let mut dex = Dex::build(Generation::Gen9).unwrap();
+        let id = |kind: EntryKind, name: &str| dex.maps.get(kind)[&dex::to_id(name)];
+        let incineroar = id(EntryKind::Species, "Incineroar");
+        let smeargle = id(EntryKind::Species, "Smeargle");
+        let fake_out = id(EntryKind::Move, "Fake Out");
+        let sketch = id(EntryKind::Move, "Sketch");
+        dex.learnsets.insert(incineroar, HashSet::from([fake_out]));
+        dex.learnsets.insert(smeargle, HashSet::from([sketch]));
+
+        let team = team(
+            "Incineroar\n- Fake Out\n- Spore\n\n\
+             Smeargle\n- Spore\n\n\
+             Amoonguss\n- Spore"
+        );
+        // amoonguss has no learnset here, so it isn't checked
+        assert_eq!(validate_team(&dex, &team, &GEN9_OU), [Violation::IllegalMove {
+            slot: 0,
+            species: "Incineroar".into(),
+            name: "Spore".into(),
+        }]);
+    }
+
+    // the example from the request, against the learnsets we ship
+    #[test]
+    #[ignore = "needs dex/learnsets.bin, run `npm run learnsets` in dex_build"]
+    fn test_shipped_learnsets() {
+        let dex = crate::get_dex();
+        let team = team("Incineroar\n- Fake Out\n- Spore\n\nSmeargle\n- Spore");
+        assert_eq!(validate_team(dex, &team, &GEN9_OU), [Violation::IllegalMove {
+            slot: 0,
+            species: "Incineroar".into(),
+            name: "Spore".into(),
+        }]);
+    }
+
+    #[test]
+    #[ignore = "needs dex/species.txt, run `npm run species` in dex_build"]
+    fn test_species_clause_formes() {
+        let dex = crate::get_dex();
+        let team = team(
+            "Urshifu-Rapid-Strike\n\nUrshifu-Rapid-Strike\n\nUrshifu\n\n\
+             Ogerpon-Wellspring\n\nOgerpon-Cornerstone\n\nRotom-Wash"
+        );
+        let clause = |slot, name: &str| Violation::SpeciesClause { slot, name: name.into() };
+        assert_eq!(validate_team(dex, &team, &GEN9_VGC_REG_G), [
+            clause(1, "Urshifu-Rapid-Strike"),
+            clause(2, "Urshifu"),
+            clause(4, "Ogerpon-Cornerstone"),
+        ]);
+    }
+
+    #[test]
+    fn test_species_data() {
+        let mut dex = Dex::build(Generation::Gen9).unwrap();
+        let id = |name: &str| dex.maps.names[&dex::to_id(name)];
+        let file = format!(
+            "{}\t50/50/77/95/77/91\tElectric/Ghost\tLevitate//\t3\t{0}\n\
+             {}\t50/65/107/105/107/86\tElectric/Water\tLevitate//\t3\t{0}\n",
+            id("Rotom"),
+            id("Rotom-Wash"),
+        );
+        dex.species = crate::species::parse_species(&file, &dex.maps.abilities, true).unwrap();
+
+        let team = team("Rotom\nAbility: Levitate\n\nRotom-Wash\nAbility: Intimidate");
+        assert_eq!(validate_team(&dex, &team, &GEN9_OU), [
+            Violation::SpeciesClause { slot: 1, name: "Rotom-Wash".into() },
+            Violation::IllegalAbility {
+                slot: 1,
+                species: "Rotom-Wash".into(),
+                name: "Intimidate".into(),
+            },
+        ]);
+    }
+}